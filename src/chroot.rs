@@ -7,7 +7,8 @@ use std::process::{Command, Stdio};
 //    path: PathBuf,
 //}
 
-pub fn create(path: PathBuf) -> Result<(), Box<dyn Error>> {
+#[allow(dead_code)]
+pub fn create(_path: PathBuf) -> Result<(), Box<dyn Error>> {
     // check if devtools are installed
     // check if path exists (create if not, retreat if path/root exists)
 
@@ -1,5 +1,4 @@
 use std::cell::RefCell;
-use std::error::Error;
 use std::path::PathBuf;
 use std::rc::Rc;
 
@@ -7,14 +6,19 @@ use crate::pkgbuild::Pkgbuild;
 use crate::settings::Settings;
 use crate::update::Update;
 
+type ShellexpandContext = Box<dyn Fn(&str) -> Result<Option<String>, String>>;
+
 // context for working with a particular PKGBUILD
 pub struct Context {
     config: Settings,
     pkgbuild: Option<Rc<RefCell<Pkgbuild>>>,
     pkgbuild_path: Option<PathBuf>,
-    pkgname: Option<String>,
+    pkgbase: Option<String>,
+    pkgnames: Vec<String>,
     current_version: String,
-    update: Option<Update>,
+    new_version: Option<String>,
+    // Overrides the commit message template from config
+    commit_message: Option<String>,
 }
 
 impl Context {
@@ -23,9 +27,11 @@ impl Context {
             config,
             pkgbuild: None,
             pkgbuild_path: None,
-            update: None,
-            pkgname: None,
+            pkgbase: None,
+            pkgnames: Vec::new(),
             current_version: String::from(""),
+            new_version: None,
+            commit_message: None,
         }
     }
 
@@ -40,7 +46,17 @@ impl Context {
     }
 
     pub fn set_update(mut self, update: &Update) -> Self {
-        self.update = Some(update.clone());
+        self.new_version = Some(update.version.clone());
+        self
+    }
+
+    pub fn set_new_version(mut self, new_version: String) -> Self {
+        self.new_version = Some(new_version);
+        self
+    }
+
+    pub fn set_commit_message(mut self, commit_message: String) -> Self {
+        self.commit_message = Some(commit_message);
         self
     }
 
@@ -49,8 +65,13 @@ impl Context {
         self
     }
 
-    pub fn set_pkgname(mut self, pkgname: String) -> Self {
-        self.pkgname = Some(pkgname);
+    pub fn set_pkgbase(mut self, pkgbase: String) -> Self {
+        self.pkgbase = Some(pkgbase);
+        self
+    }
+
+    pub fn set_pkgnames(mut self, pkgnames: Vec<String>) -> Self {
+        self.pkgnames = pkgnames;
         self
     }
 
@@ -58,6 +79,13 @@ impl Context {
         self.config.clone()
     }
 
+    pub fn commit_message(&self) -> String {
+        match &self.commit_message {
+            Some(commit_message) => commit_message.clone(),
+            None => self.config.commit_message(),
+        }
+    }
+
     pub fn pkgbuild(&self) -> Option<Rc<RefCell<Pkgbuild>>> {
         self.pkgbuild.clone()
     }
@@ -66,16 +94,19 @@ impl Context {
         self.pkgbuild_path.clone()
     }
 
-    pub fn shellexpand_context(&self) -> Box<dyn Fn(&str) -> Result<Option<String>, String>> {
-        let update = self.update.clone().unwrap();
+    pub fn shellexpand_context(&self) -> ShellexpandContext {
+        let new_version = self.new_version.clone().unwrap();
         let current_version = self.current_version.clone();
-        let pkgname = self.pkgname.clone();
+        let pkgbase = self.pkgbase.clone();
+        let pkgnames = self.pkgnames.join(" ");
         Box::new(move |s| match s {
-            "pkgname" => Ok(Some(pkgname.as_ref().unwrap().clone())),
+            // pkgname stays a single name for split packages, so templates read the same
+            "pkgname" | "pkgbase" => Ok(Some(pkgbase.as_ref().unwrap().clone())),
+            "pkgnames" => Ok(Some(pkgnames.clone())),
             "old_version" => {
                 Ok(Some(current_version.clone())) // too many clones
             }
-            "new_version" => Ok(Some(update.version.clone())),
+            "new_version" => Ok(Some(new_version.clone())),
             _ => Ok(None),
         })
     }
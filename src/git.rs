@@ -1,9 +1,12 @@
 use crate::context::Context;
+use crate::pkgbuild;
+use crate::srcinfo;
 use std::error::Error;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::str;
 
+#[allow(dead_code)]
 pub fn repo_root(path: &Path) -> Result<PathBuf, Box<dyn Error>> {
     let git = Command::new("git")
         .current_dir(path.to_str().unwrap())
@@ -19,13 +22,30 @@ pub fn repo_root(path: &Path) -> Result<PathBuf, Box<dyn Error>> {
 }
 
 pub fn commit(context: &Context) -> Result<(), Box<dyn Error>> {
-    let message_template = context.config().commit_message();
+    let message_template = context.commit_message();
     let pkgbuild_path = context.pkgbuild_path().unwrap();
     let path = pkgbuild_path.parent().unwrap();
     let message = shellexpand::env_with_context(&message_template, &context.shellexpand_context())
         .unwrap()
         .into_owned();
 
+    // AUR rejects pushes where .SRCINFO doesn't describe the PKGBUILD
+    if pkgbuild::srcinfo_path(&pkgbuild_path)?.exists() {
+        let (mismatches, unchecked) = srcinfo::lint(&pkgbuild_path)?;
+        for mismatch in &unchecked {
+            println!("Unable to check, computed by the shell: {}", mismatch);
+        }
+        if !mismatches.is_empty() {
+            let lines: Vec<String> = mismatches.iter().map(|m| m.to_string()).collect();
+            let error: Box<dyn Error> = format!(
+                "Refusing to commit, .SRCINFO doesn't match PKGBUILD:\n{}",
+                lines.join("\n")
+            )
+            .into();
+            return Err(error);
+        }
+    }
+
     // check if staging is empty
     // add PKGBUILD & .SRCINFO if needed
     let git = Command::new("git")
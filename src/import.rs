@@ -0,0 +1,416 @@
+// Translates other tools' configs into upstreams of `[packages.<pkgbase>]`
+
+use regex::Regex;
+use toml::value::{Table, Value};
+
+use std::error::Error;
+
+// nvchecker options understood by every translated source
+const COMMON_OPTIONS: [&str; 1] = ["source"];
+
+// Result of an import, a config section and what got lost on the way
+pub struct Import {
+    pub config: Value,
+    // Entries which have no pacops counterpart
+    pub untranslated: Vec<String>,
+    // Options of translated entries which were dropped
+    pub ignored: Vec<String>,
+}
+
+// Entries of nvchecker.toml, `[__config__]` aside
+pub fn nvchecker(text: &str) -> Result<Import, Box<dyn Error>> {
+    let entries: Table = toml::from_str(text)?;
+    let mut packages = Table::new();
+    let mut untranslated = Vec::new();
+    let mut ignored = Vec::new();
+    for (name, entry) in &entries {
+        if name == "__config__" {
+            continue;
+        }
+        let entry = match entry.as_table() {
+            Some(entry) => entry,
+            None => {
+                untranslated.push(format!("{}: not a table", name));
+                continue;
+            }
+        };
+        match nvchecker_entry(entry) {
+            Ok((upstream, used)) => {
+                for option in entry.keys() {
+                    if !used.contains(&option.as_str())
+                        && !COMMON_OPTIONS.contains(&option.as_str())
+                    {
+                        ignored.push(format!("{}: {}", name, option));
+                    }
+                }
+                let mut package = Table::new();
+                package.insert(
+                    String::from("upstream"),
+                    Value::Array(vec![Value::Table(upstream)]),
+                );
+                packages.insert(name.clone(), Value::Table(package));
+            }
+            Err(why) => untranslated.push(format!("{}: {}", name, why)),
+        }
+    }
+
+    let mut config = Table::new();
+    config.insert(String::from("packages"), Value::Table(packages));
+    Ok(Import {
+        config: Value::Table(config),
+        untranslated,
+        ignored,
+    })
+}
+
+// Upstream for an entry along with options it used
+fn nvchecker_entry(entry: &Table) -> Result<(Table, Vec<&'static str>), String> {
+    let option = |name: &str| entry.get(name).and_then(|value| value.as_str());
+    let flag = |name: &str| {
+        entry
+            .get(name)
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false)
+    };
+    let required = |name: &str| match option(name) {
+        Some(value) => Ok(value),
+        None => Err(format!("`{}` is missing", name)),
+    };
+
+    let mut upstream = Table::new();
+    let mut set = |key: &str, value: String| {
+        upstream.insert(key.to_string(), Value::String(value));
+    };
+    let source = required("source")?;
+    let used = match source {
+        "github" => {
+            let kind = if flag("use_max_tag") {
+                "github-tag"
+            } else if flag("use_latest_release") {
+                "github-release"
+            } else {
+                return Err(String::from(
+                    "versions from commits aren't supported, only releases and tags",
+                ));
+            };
+            set("kind", kind.to_string());
+            set("url", format!("https://github.com/{}", required("github")?));
+            // A bare repository URL has no tag to take the prefix from
+            set(
+                "tag_prefix",
+                option("prefix").unwrap_or_default().to_string(),
+            );
+            vec!["github", "use_max_tag", "use_latest_release", "prefix"]
+        }
+        "gitlab" | "gitea" => {
+            if !flag("use_max_tag") {
+                return Err(String::from(
+                    "versions from commits aren't supported, only tags",
+                ));
+            }
+            let host = option("host").unwrap_or(if source == "gitlab" {
+                "gitlab.com"
+            } else {
+                "gitea.com"
+            });
+            set("kind", source.to_string());
+            set("url", format!("https://{}/{}", host, required(source)?));
+            // A bare repository URL has no tag to take the prefix from
+            set(
+                "tag_prefix",
+                option("prefix").unwrap_or_default().to_string(),
+            );
+            vec!["gitlab", "gitea", "host", "use_max_tag", "prefix"]
+        }
+        "pypi" => {
+            let project = required("pypi")?;
+            let initial = match project.chars().next() {
+                Some(initial) => initial,
+                None => return Err(String::from("`pypi` is empty")),
+            };
+            set("kind", String::from("pypi"));
+            set(
+                "url",
+                format!(
+                    "https://pypi.io/packages/source/{}/{}/{}-${{pkgver}}.tar.gz",
+                    initial, project, project
+                ),
+            );
+            vec!["pypi"]
+        }
+        "cratesio" => {
+            let name = required("cratesio")?;
+            set("kind", String::from("crates"));
+            set(
+                "url",
+                format!(
+                    "https://static.crates.io/crates/{}/{}-${{pkgver}}.crate",
+                    name, name
+                ),
+            );
+            vec!["cratesio"]
+        }
+        "npm" => {
+            let name = required("npm")?;
+            let basename = name.rsplit('/').next().unwrap();
+            set("kind", String::from("npm"));
+            set(
+                "url",
+                format!(
+                    "https://registry.npmjs.org/{}/-/{}-${{pkgver}}.tgz",
+                    name, basename
+                ),
+            );
+            vec!["npm"]
+        }
+        "regex" => {
+            let regex = version_group(required("regex")?);
+            // Python's lookarounds and backreferences don't compile here
+            if let Err(why) = Regex::new(&regex) {
+                return Err(format!("`regex` isn't supported: {}", why));
+            }
+            set("kind", String::from("regex"));
+            set("url", required("url")?.to_string());
+            set("regex", regex);
+            vec!["url", "regex"]
+        }
+        "cmd" => {
+            set("kind", String::from("command"));
+            set("command", required("cmd")?.to_string());
+            vec!["cmd"]
+        }
+        source => return Err(format!("source `{}` isn't supported", source)),
+    };
+    Ok((upstream, used))
+}
+
+// nvchecker takes the first group, or the whole match without one
+fn version_group(regex: &str) -> String {
+    let mut escaped = false;
+    let mut class = false;
+    for (position, c) in regex.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '[' => class = true,
+            ']' => class = false,
+            '(' if !class && !regex[position + 1..].starts_with('?') => {
+                return format!(
+                    "{}(?P<version>{}",
+                    &regex[..position],
+                    &regex[position + 1..]
+                );
+            }
+            _ => {}
+        }
+    }
+    format!("(?P<version>{})", regex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Upstream an entry translates to, as `key=value` pairs
+    fn upstream(import: &Import, name: &str) -> Vec<(String, String)> {
+        import.config["packages"][name]["upstream"][0]
+            .as_table()
+            .unwrap()
+            .iter()
+            .map(|(key, value)| (key.clone(), value.as_str().unwrap().to_string()))
+            .collect()
+    }
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        let mut pairs: Vec<(String, String)> = pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        pairs.sort();
+        pairs
+    }
+
+    #[test]
+    fn sources_translate() {
+        let import = nvchecker(
+            r#"
+[__config__]
+oldver = "old.json"
+
+[foo]
+source = "github"
+github = "o/foo"
+use_max_tag = true
+prefix = "v"
+
+[bar]
+source = "github"
+github = "o/bar"
+use_latest_release = true
+
+[baz]
+source = "gitlab"
+gitlab = "o/baz"
+host = "gitlab.e.org"
+use_max_tag = true
+
+[qux]
+source = "gitea"
+gitea = "o/qux"
+use_max_tag = true
+
+[python-requests]
+source = "pypi"
+pypi = "requests"
+
+[ripgrep]
+source = "cratesio"
+cratesio = "ripgrep"
+
+[typescript]
+source = "npm"
+npm = "@types/node"
+
+[page]
+source = "regex"
+url = "https://e.org/download"
+regex = 'page-([\d.]+)\.tar\.gz'
+
+[cmd]
+source = "cmd"
+cmd = "echo 1.0"
+"#,
+        )
+        .unwrap();
+        assert!(import.untranslated.is_empty());
+        assert!(import.ignored.is_empty());
+        let expected: [(&str, &[(&str, &str)]); 9] = [
+            (
+                "foo",
+                &[
+                    ("kind", "github-tag"),
+                    ("url", "https://github.com/o/foo"),
+                    ("tag_prefix", "v"),
+                ],
+            ),
+            (
+                "bar",
+                &[
+                    ("kind", "github-release"),
+                    ("url", "https://github.com/o/bar"),
+                    ("tag_prefix", ""),
+                ],
+            ),
+            (
+                "baz",
+                &[
+                    ("kind", "gitlab"),
+                    ("url", "https://gitlab.e.org/o/baz"),
+                    ("tag_prefix", ""),
+                ],
+            ),
+            (
+                "qux",
+                &[
+                    ("kind", "gitea"),
+                    ("url", "https://gitea.com/o/qux"),
+                    ("tag_prefix", ""),
+                ],
+            ),
+            (
+                "python-requests",
+                &[
+                    ("kind", "pypi"),
+                    (
+                        "url",
+                        "https://pypi.io/packages/source/r/requests/requests-${pkgver}.tar.gz",
+                    ),
+                ],
+            ),
+            (
+                "ripgrep",
+                &[
+                    ("kind", "crates"),
+                    (
+                        "url",
+                        "https://static.crates.io/crates/ripgrep/ripgrep-${pkgver}.crate",
+                    ),
+                ],
+            ),
+            (
+                "typescript",
+                &[
+                    ("kind", "npm"),
+                    (
+                        "url",
+                        "https://registry.npmjs.org/@types/node/-/node-${pkgver}.tgz",
+                    ),
+                ],
+            ),
+            (
+                "page",
+                &[
+                    ("kind", "regex"),
+                    ("url", "https://e.org/download"),
+                    ("regex", "page-(?P<version>[\\d.]+)\\.tar\\.gz"),
+                ],
+            ),
+            ("cmd", &[("kind", "command"), ("command", "echo 1.0")]),
+        ];
+        for (name, expected) in expected.iter() {
+            let mut translated = upstream(&import, name);
+            translated.sort();
+            assert_eq!(translated, pairs(expected), "{}", name);
+        }
+        assert!(import.config["packages"].get("__config__").is_none());
+    }
+
+    #[test]
+    fn lost_entries_and_options_are_reported() {
+        let import = nvchecker(
+            r#"
+[commits]
+source = "github"
+github = "o/commits"
+
+[aur]
+source = "aur"
+
+[missing]
+source = "pypi"
+
+[python]
+source = "regex"
+url = "https://e.org"
+regex = 'foo-(\d+)(?=\.tar)'
+
+[options]
+source = "cratesio"
+cratesio = "foo"
+exclude_regex = ".*beta.*"
+"#,
+        )
+        .unwrap();
+        let mut untranslated: Vec<&str> = import
+            .untranslated
+            .iter()
+            .map(|entry| entry.split(':').next().unwrap())
+            .collect();
+        untranslated.sort();
+        assert_eq!(untranslated, ["aur", "commits", "missing", "python"]);
+        assert_eq!(import.ignored, ["options: exclude_regex"]);
+        assert!(import.config["packages"].get("python").is_none());
+    }
+
+    #[test]
+    fn first_group_holds_the_version() {
+        assert_eq!(version_group(r"foo-([\d.]+)"), r"foo-(?P<version>[\d.]+)");
+        assert_eq!(version_group(r"foo-[\d.]+"), r"(?P<version>foo-[\d.]+)");
+        assert_eq!(
+            version_group(r"(?:v|release-)(\d+)"),
+            r"(?:v|release-)(?P<version>\d+)"
+        );
+        assert_eq!(version_group(r"\(([0-9.]+)\)"), r"\((?P<version>[0-9.]+)\)");
+        assert_eq!(version_group(r"[(]v([0-9.]+)"), r"[(]v(?P<version>[0-9.]+)");
+    }
+}
@@ -1,9 +1,10 @@
 extern crate clap;
 extern crate reqwest;
-extern crate version_compare;
 
 use std::error::Error;
-use std::{path::PathBuf, str::FromStr};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 use clap::{App, AppSettings, Arg, SubCommand};
 
@@ -12,10 +13,13 @@ use crate::settings::Settings;
 mod chroot;
 mod context;
 mod git;
+mod import;
 mod pkgbuild;
 mod settings;
 mod source;
+mod srcinfo;
 mod update;
+mod version;
 
 fn main() -> Result<(), Box<dyn Error>> {
     let matches = App::new("PacOps")
@@ -50,6 +54,11 @@ fn main() -> Result<(), Box<dyn Error>> {
                         .long("commit")
                         .help("Commits the change to a local git repo."),
                 )
+                .arg(
+                    Arg::with_name("bump-rel").long("bump-rel").help(
+                        "Increments pkgrel to rebuild the current version instead of updating.",
+                    ),
+                )
                 .arg(
                     Arg::with_name("srcinfo")
                         .long("srcinfo")
@@ -71,6 +80,66 @@ fn main() -> Result<(), Box<dyn Error>> {
                         .takes_value(false),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("srcinfo")
+                .about("Generates .SRCINFO without makepkg")
+                .arg(
+                    Arg::with_name("PKGBUILD")
+                        .help("Sets the PKGBUILD file to use.")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("print")
+                        .long("print")
+                        .help("Prints .SRCINFO instead of writing it next to the PKGBUILD."),
+                )
+                .arg(
+                    Arg::with_name("compare")
+                        .long("compare")
+                        .help("Compares the result with `makepkg --printsrcinfo` output."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("lint")
+                .about("Checks packaging files for common mistakes")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("srcinfo")
+                        .about("Reports where .SRCINFO disagrees with the PKGBUILD")
+                        .arg(
+                            Arg::with_name("PKGBUILD")
+                                .help("Sets the PKGBUILD file to use.")
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::with_name("fix")
+                                .long("fix")
+                                .help("Regenerates .SRCINFO when it's out of date."),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("import")
+                .about("Translates other tools' configs into upstreams")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("nvchecker")
+                        .about("Imports entries of an nvchecker.toml")
+                        .arg(
+                            Arg::with_name("FILE")
+                                .help("Sets the nvchecker.toml file to use.")
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::with_name("output")
+                                .short("o")
+                                .long("output")
+                                .value_name("FILE")
+                                .help("Writes the upstreams to a file instead of printing them.")
+                                .takes_value(true),
+                        ),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("chroot")
                 .about("Manipulate chroots")
@@ -108,6 +177,32 @@ fn main() -> Result<(), Box<dyn Error>> {
         };
     };
 
+    if let Some(matches) = matches.subcommand_matches("srcinfo") {
+        let path = Path::new(matches.value_of("PKGBUILD").unwrap());
+        if matches.is_present("compare") {
+            compare_srcinfo(path)?;
+        } else if matches.is_present("print") {
+            let pkgbuild = pkgbuild::Pkgbuild::from_file(path.to_str().unwrap())?;
+            print!("{}", srcinfo::Srcinfo::from_pkgbuild(&pkgbuild.borrow()));
+        } else {
+            pkgbuild::srcinfo(path)?;
+        }
+    };
+
+    if let Some(matches) = matches.subcommand_matches("lint") {
+        if let Some(matches) = matches.subcommand_matches("srcinfo") {
+            let path = Path::new(matches.value_of("PKGBUILD").unwrap());
+            lint_srcinfo(path, matches.is_present("fix"))?;
+        };
+    };
+
+    if let Some(matches) = matches.subcommand_matches("import") {
+        if let Some(matches) = matches.subcommand_matches("nvchecker") {
+            let path = Path::new(matches.value_of("FILE").unwrap());
+            import_nvchecker(path, matches.value_of("output").map(Path::new))?;
+        };
+    };
+
     if let Some(matches) = matches.subcommand_matches("package") {
         if matches.is_present("commit") {
             config.set("commit", true)?;
@@ -134,7 +229,11 @@ fn main() -> Result<(), Box<dyn Error>> {
             let config: Settings = config.try_into().unwrap();
             println!("{:?}", config);
 
-            update(context)
+            if matches.is_present("bump-rel") {
+                bump_rel(context)
+            } else {
+                update(context)
+            }
         }
     };
     Ok(())
@@ -145,31 +244,45 @@ fn update(context: context::Context) {
     let config = context.config();
     let path = pkgbuild.borrow().path().as_ref().unwrap().clone();
     let current_version = pkgbuild.borrow().version().as_ref().unwrap().clone();
-    let updates = pkgbuild.borrow().check_for_updates().unwrap();
+    let upstreams = source::upstream::load(&path, pkgbuild.borrow().pkgbase(), &config).unwrap();
+    pkgbuild.borrow_mut().set_upstreams(&upstreams).unwrap();
+    let updates = pkgbuild.borrow().check_for_updates(&config).unwrap();
     for update in &updates {
         println!(
             "Update available\n\t{} over {}",
             update.version, current_version
         );
         let mut pkgbuild = pkgbuild.borrow_mut();
-        pkgbuild.set_version(update.version.clone());
-        let new_hash = update.hash(pkgbuild.hash_alg()).unwrap();
-        pkgbuild.set_hash(update.source_index, new_hash).unwrap();
+        let mut edits = pkgbuild.set_version(update.version.clone()).unwrap();
+        // Every checksum array has to be updated or makepkg fails validation
+        let hash_algs = pkgbuild
+            .hash_algs(&update.source_arch, update.source_index)
+            .unwrap();
+        let new_hashes = if hash_algs.is_empty() {
+            Vec::new()
+        } else {
+            update.hashes(&hash_algs).unwrap()
+        };
+        for (hash_alg, new_hash) in hash_algs.into_iter().zip(new_hashes) {
+            edits.append(
+                &mut pkgbuild
+                    .set_hash(hash_alg, &update.source_arch, update.source_index, new_hash)
+                    .unwrap(),
+            );
+        }
+        for edit in &edits {
+            print!("{}", edit);
+        }
         pkgbuild.to_file(path.as_path().to_str().unwrap()).unwrap();
     }
     if !updates.is_empty() {
-        // test build
-        let pkgbuild_dir = path.parent().unwrap();
-        pkgbuild::update_build_env(config.clone()).unwrap();
-        pkgbuild::build(pkgbuild_dir, &config);
-        if let true = config.srcinfo() {
-            pkgbuild::srcinfo(&path).unwrap();
-        }
+        test_build(&path, &config);
         let pkgbuild = pkgbuild.borrow_mut();
         let mut context = context::Context::new(config.clone())
             .set_pkgbuild_path(path)
             // TODO: figure out a way to compose a commit message for minor updates
-            .set_pkgname(pkgbuild.pkgname().clone())
+            .set_pkgbase(pkgbuild.pkgbase().clone())
+            .set_pkgnames(pkgbuild.pkgnames().clone())
             .set_current_version(current_version);
         for update in &updates {
             context = context.set_update(update);
@@ -196,3 +309,109 @@ fn update(context: context::Context) {
         println!("No update available")
     }
 }
+
+// Increments pkgrel to rebuild the same upstream version
+fn bump_rel(context: context::Context) {
+    let pkgbuild = context.pkgbuild().unwrap();
+    let config = context.config();
+    let path = pkgbuild.borrow().path().as_ref().unwrap().clone();
+    let current_version = pkgbuild.borrow().pkg_version().to_string();
+    {
+        let mut pkgbuild = pkgbuild.borrow_mut();
+        for edit in &pkgbuild.bump_pkgrel().unwrap() {
+            print!("{}", edit);
+        }
+        pkgbuild.to_file(path.as_path().to_str().unwrap()).unwrap();
+    }
+    test_build(&path, &config);
+    let pkgbuild = pkgbuild.borrow();
+    let context = context::Context::new(config.clone())
+        .set_pkgbuild_path(path)
+        .set_pkgbase(pkgbuild.pkgbase().clone())
+        .set_pkgnames(pkgbuild.pkgnames().clone())
+        .set_current_version(current_version)
+        .set_new_version(pkgbuild.pkg_version().to_string())
+        .set_commit_message(config.bump_rel_commit_message());
+    if config.commit() {
+        git::commit(&context).unwrap();
+        if config.push() {
+            git::push(&context).unwrap();
+        }
+    }
+}
+
+fn test_build(path: &Path, config: &Settings) {
+    let pkgbuild_dir = path.parent().unwrap();
+    pkgbuild::update_build_env(config.clone()).unwrap();
+    let packages = pkgbuild::build(pkgbuild_dir, config).unwrap();
+    for package in &packages {
+        println!("Built {}", package.display());
+    }
+    if config.srcinfo() {
+        pkgbuild::srcinfo(path).unwrap();
+    }
+}
+
+fn lint_srcinfo(path: &Path, fix: bool) -> Result<(), Box<dyn Error>> {
+    let (mismatches, unchecked) = srcinfo::lint(path)?;
+    for mismatch in &unchecked {
+        println!("Unable to check, computed by the shell: {}", mismatch);
+    }
+    if mismatches.is_empty() {
+        println!(".SRCINFO is up to date");
+        return Ok(());
+    }
+    for mismatch in &mismatches {
+        println!("{}", mismatch);
+    }
+    if fix {
+        pkgbuild::srcinfo(path)?;
+        println!("Regenerated .SRCINFO");
+        return Ok(());
+    }
+    let error: Box<dyn Error> = format!(
+        ".SRCINFO is out of date in {} place(s), rerun with --fix to regenerate it",
+        mismatches.len()
+    )
+    .into();
+    Err(error)
+}
+
+// Upstreams go to stdout unless written to a file, everything else to stderr
+fn import_nvchecker(path: &Path, output: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    let import = import::nvchecker(&fs::read_to_string(path)?)?;
+    let config = toml::to_string_pretty(&import.config)?;
+    match output {
+        Some(output) => {
+            fs::write(output, config)?;
+            eprintln!("Wrote upstreams to {}", output.display());
+        }
+        None => print!("{}", config),
+    }
+    for ignored in &import.ignored {
+        eprintln!("Ignored option {}", ignored);
+    }
+    for untranslated in &import.untranslated {
+        eprintln!("Unable to translate {}", untranslated);
+    }
+    Ok(())
+}
+
+// Shows where generated .SRCINFO differs from makepkg's
+fn compare_srcinfo(path: &Path) -> Result<(), Box<dyn Error>> {
+    let pkgbuild = pkgbuild::Pkgbuild::from_file(path.to_str().unwrap())?;
+    let generated = srcinfo::Srcinfo::from_pkgbuild(&pkgbuild.borrow()).to_string();
+    let expected = srcinfo::makepkg(path.parent().unwrap())?;
+    let diff = srcinfo::diff(&expected, &generated);
+    if diff.is_empty() {
+        println!("Generated .SRCINFO matches makepkg output");
+        return Ok(());
+    }
+    println!("--- makepkg\n+++ pacops");
+    for line in &diff {
+        println!("{}", line);
+    }
+    let error: Box<dyn Error> =
+        String::from("Generated .SRCINFO differs from makepkg output").into();
+    Err(error)
+}
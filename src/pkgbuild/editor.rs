@@ -0,0 +1,122 @@
+// Rewrites single values of a PKGBUILD in place.
+//
+// Only the bytes of the targeted word are replaced, so whitespace, comments and
+// the rest of the file stay exactly as they were. The new value is quoted the
+// same way as the old one.
+
+use std::fmt;
+
+use super::parser::Word;
+
+#[derive(Clone, Debug)]
+pub struct Edit {
+    // 1-based line of the edited word
+    pub line: usize,
+    pub before: String,
+    pub after: String,
+}
+
+impl fmt::Display for Edit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "@@ line {} @@", self.line)?;
+        for line in self.before.lines() {
+            writeln!(f, "-{}", line)?;
+        }
+        for line in self.after.lines() {
+            writeln!(f, "+{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+// Replaces a word with a new value, returns the new text and the edit made
+pub fn replace(raw: &str, word: &Word, value: &str) -> (String, Edit) {
+    let original = &raw[word.span.clone()];
+    let replacement = quote_like(original, value);
+
+    let line_start = raw[..word.span.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = raw[word.span.end..]
+        .find('\n')
+        .map_or(raw.len(), |i| word.span.end + i);
+
+    let mut edited = String::with_capacity(raw.len() + replacement.len());
+    edited.push_str(&raw[..word.span.start]);
+    edited.push_str(&replacement);
+    edited.push_str(&raw[word.span.end..]);
+
+    let edit = Edit {
+        line: raw[..word.span.start].matches('\n').count() + 1,
+        before: raw[line_start..line_end].to_string(),
+        after: edited[line_start..line_end + replacement.len() - original.len()].to_string(),
+    };
+    (edited, edit)
+}
+
+// Quotes a value the way the original word was quoted
+fn quote_like(original: &str, value: &str) -> String {
+    let single_quoted =
+        original.len() > 1 && original.starts_with('\'') && original.ends_with('\'');
+    let double_quoted = original.len() > 1 && original.starts_with('"') && original.ends_with('"');
+    if single_quoted && !value.contains('\'') {
+        format!("'{}'", value)
+    } else if single_quoted || double_quoted || value.is_empty() || value.chars().any(needs_quoting)
+    {
+        format!("\"{}\"", escape_double_quoted(value))
+    } else {
+        value.to_string()
+    }
+}
+
+fn escape_double_quoted(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if "$`\"\\".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn needs_quoting(c: char) -> bool {
+    c.is_whitespace() || "'\"\\$`;&|()<>#*?[]{}~!".contains(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pkgbuild::parser;
+
+    fn replace_first(raw: &str, name: &str, value: &str) -> (String, Edit) {
+        let script = parser::parse(raw).unwrap();
+        let word = script.words(name)[0].clone();
+        replace(raw, &word, value)
+    }
+
+    #[test]
+    fn bytes_around_the_word_are_kept() {
+        let raw = "# Maintainer: someone\n  pkgver=1.0   # keep me\n\npkgrel=3\n";
+        let (edited, edit) = replace_first(raw, "pkgver", "1.1");
+        assert_eq!(
+            edited,
+            "# Maintainer: someone\n  pkgver=1.1   # keep me\n\npkgrel=3\n"
+        );
+        assert_eq!(edit.line, 2);
+        assert_eq!(edit.before, "  pkgver=1.0   # keep me");
+        assert_eq!(edit.after, "  pkgver=1.1   # keep me");
+    }
+
+    #[test]
+    fn quoting_is_kept() {
+        let raw = "sha256sums=('aa'\n            \"bb\" cc)\n";
+        let script = parser::parse(raw).unwrap();
+        let words = script.words("sha256sums");
+        let (edited, _) = replace(raw, words[0], "dd");
+        assert_eq!(edited, "sha256sums=('dd'\n            \"bb\" cc)\n");
+        let (edited, _) = replace(raw, words[1], "ee");
+        assert_eq!(edited, "sha256sums=('aa'\n            \"ee\" cc)\n");
+        let (edited, edit) = replace(raw, words[2], "a b");
+        assert_eq!(edited, "sha256sums=('aa'\n            \"bb\" \"a b\")\n");
+        assert_eq!(edit.line, 2);
+    }
+}
@@ -0,0 +1,810 @@
+extern crate shellexpand;
+
+use crate::chroot;
+use crate::settings::{Build, Settings};
+use crate::source::upstream::Upstream;
+use crate::source::Source;
+use crate::srcinfo::Srcinfo;
+use crate::update::Update;
+use crate::version::{vercmp, PkgVersion};
+
+use editor::Edit;
+use parser::{Assignment, Script, Value};
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+use std::str;
+
+use regex::Regex;
+
+mod editor;
+mod parser;
+
+pub struct Pkgbuild {
+    raw: String,
+    script: Script,
+    variables: HashMap<String, Vec<String>>,
+    pkgbase: String,
+    pkgnames: Vec<String>,
+    version: Option<String>,
+    sources: Vec<Source>,
+    // One per checksum array kind present, e.g. both sha256sums and b2sums
+    hashsums: Vec<HashSums>,
+    path: Option<PathBuf>,
+}
+
+pub struct HashSums {
+    // Checksums of `source` are under None, the ones of `source_<arch>` under the arch
+    hashes: HashMap<Option<String>, Vec<String>>,
+    alg: HashAlg,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HashAlg {
+    B2,
+    SHA1,
+    SHA224,
+    SHA256,
+    SHA384,
+    SHA512,
+    MD5,
+}
+
+impl HashAlg {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlg::B2 => "b2",
+            HashAlg::SHA1 => "sha1",
+            HashAlg::SHA224 => "sha224",
+            HashAlg::SHA256 => "sha256",
+            HashAlg::SHA384 => "sha384",
+            HashAlg::SHA512 => "sha512",
+            HashAlg::MD5 => "md5",
+        }
+    }
+
+    // Reverse of `name()`, `sha` is what older repositories call sha1
+    pub fn from_name(name: &str) -> Option<HashAlg> {
+        match name {
+            "b2" => Some(HashAlg::B2),
+            "sha" | "sha1" => Some(HashAlg::SHA1),
+            "sha224" => Some(HashAlg::SHA224),
+            "sha256" => Some(HashAlg::SHA256),
+            "sha384" => Some(HashAlg::SHA384),
+            "sha512" => Some(HashAlg::SHA512),
+            "md5" => Some(HashAlg::MD5),
+            _ => None,
+        }
+    }
+}
+
+impl HashSums {
+    fn new(line_prefix: String, hashes: HashMap<Option<String>, Vec<String>>) -> Option<HashSums> {
+        match line_prefix.as_str() {
+            "md5" => Some(HashSums {
+                hashes,
+                alg: HashAlg::MD5,
+            }),
+            "b2" => Some(HashSums {
+                hashes,
+                alg: HashAlg::B2,
+            }),
+            "sha1" => Some(HashSums {
+                hashes,
+                alg: HashAlg::SHA1,
+            }),
+            "sha224" => Some(HashSums {
+                hashes,
+                alg: HashAlg::SHA224,
+            }),
+            "sha256" => Some(HashSums {
+                hashes,
+                alg: HashAlg::SHA256,
+            }),
+            "sha384" => Some(HashSums {
+                hashes,
+                alg: HashAlg::SHA384,
+            }),
+            "sha512" => Some(HashSums {
+                hashes,
+                alg: HashAlg::SHA512,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl Pkgbuild {
+    fn new(raw: String, path: Option<PathBuf>) -> Result<Rc<RefCell<Pkgbuild>>, Box<dyn Error>> {
+        let script = parser::parse(&raw)?;
+        let variables = evaluate(&script);
+        let version = variables.get("pkgver").and_then(|v| v.first()).cloned();
+        let pkgnames = match variables.get("pkgname") {
+            Some(pkgnames) if !pkgnames.is_empty() => pkgnames.clone(),
+            _ => return Err(String::from("PKGBUILD doesn't define pkgname").into()),
+        };
+        // Same fallback makepkg uses for packages which aren't split
+        let pkgbase = match variables.get("pkgbase").and_then(|v| v.first()) {
+            Some(pkgbase) => pkgbase.clone(),
+            None => pkgnames[0].clone(),
+        };
+        // Metapackages and alike have none, checksums are only required for updated sources
+        let hashsums = Pkgbuild::parse_hashsums(&variables);
+        let pkgb = Rc::new(RefCell::new(Pkgbuild {
+            raw,
+            script,
+            variables,
+            version,
+            pkgbase,
+            pkgnames,
+            sources: Vec::new(),
+            hashsums,
+            path,
+        }));
+        let sources = Pkgbuild::parse_sources(&pkgb);
+        pkgb.borrow_mut().set_sources(sources);
+        Ok(pkgb)
+    }
+
+    pub fn from_file(path: &str) -> Result<Rc<RefCell<Pkgbuild>>, Box<dyn Error>> {
+        let path = Path::new(path);
+        let display = path.display();
+
+        let mut file = match File::open(path) {
+            Err(why) => panic!("couldn't open {}: {}", display, why),
+            Ok(file) => file,
+        };
+
+        let mut s = String::new();
+        match file.read_to_string(&mut s) {
+            Err(why) => Err(Box::new(why)),
+            Ok(_) => Ok(Pkgbuild::new(s, Some(path.to_path_buf()))?),
+        }
+    }
+
+    // Algorithms of checksum arrays which verify a source, SKIP entries aside
+    pub fn hash_algs(
+        &self,
+        arch: &Option<String>,
+        index: usize,
+    ) -> Result<Vec<HashAlg>, Box<dyn Error>> {
+        let verified = |hashsums: &&HashSums| match hashsums.hashes.get(arch) {
+            Some(hashes) => hashes.get(index).is_some(),
+            None => false,
+        };
+        if !self.hashsums.iter().any(|hashsums| verified(&hashsums)) {
+            let variable = match arch {
+                Some(arch) => format!("source_{}", arch),
+                None => String::from("source"),
+            };
+            return Err(format!(
+                "PKGBUILD doesn't have any checksums for {}[{}]",
+                variable, index
+            )
+            .into());
+        }
+        Ok(self
+            .hashsums
+            .iter()
+            .filter(verified)
+            .filter(|hashsums| hashsums.hashes[arch][index] != "SKIP")
+            .map(|hashsums| hashsums.alg)
+            .collect())
+    }
+
+    // Sources every checksum array SKIPs aren't verified, and so aren't updated
+    pub fn is_skipped(&self, arch: &Option<String>, index: usize) -> bool {
+        let mut entries = self
+            .hashsums
+            .iter()
+            .filter_map(|hashsums| hashsums.hashes.get(arch)?.get(index))
+            .peekable();
+        entries.peek().is_some() && entries.all(|entry| entry == "SKIP")
+    }
+
+    pub fn set_version(&mut self, new_version: String) -> Result<Vec<Edit>, Box<dyn Error>> {
+        // Several sources, e.g. one per arch, may bring the same update
+        if self.version.as_ref() == Some(&new_version) {
+            return Ok(Vec::new());
+        }
+        let mut edits = vec![self.edit("pkgver", 0, &new_version)?];
+        self.version = Some(new_version);
+        // A fresh upstream release is the first build of it
+        if self.pkgrel().as_deref() != Some("1") {
+            edits.append(&mut self.set_pkgrel(String::from("1"))?);
+        }
+        Ok(edits)
+    }
+
+    pub fn version(&self) -> &Option<String> {
+        &self.version
+    }
+
+    pub fn pkgrel(&self) -> Option<String> {
+        self.variables
+            .get("pkgrel")
+            .and_then(|values| values.first())
+            .cloned()
+    }
+
+    pub fn set_pkgrel(&mut self, new_pkgrel: String) -> Result<Vec<Edit>, Box<dyn Error>> {
+        Ok(vec![self.edit("pkgrel", 0, &new_pkgrel)?])
+    }
+
+    // Increments pkgrel for a rebuild, `2` becomes `3` and `2.1` becomes `2.2`
+    pub fn bump_pkgrel(&mut self) -> Result<Vec<Edit>, Box<dyn Error>> {
+        let pkgrel = match self.pkgrel() {
+            Some(pkgrel) => pkgrel,
+            None => return Err(String::from("PKGBUILD doesn't define pkgrel").into()),
+        };
+        let (release, subrelease) = match pkgrel.split_once('.') {
+            Some((release, subrelease)) => (release, Some(subrelease)),
+            None => (pkgrel.as_str(), None),
+        };
+        let bump = |number: &str| -> Result<u64, Box<dyn Error>> {
+            match number.parse::<u64>() {
+                Ok(number) => Ok(number + 1),
+                Err(_) => Err(format!("Unable to bump pkgrel '{}'", pkgrel).into()),
+            }
+        };
+        let new_pkgrel = match subrelease {
+            Some(subrelease) => format!("{}.{}", release, bump(subrelease)?),
+            None => bump(release)?.to_string(),
+        };
+        self.set_pkgrel(new_pkgrel)
+    }
+
+    // [epoch:]pkgver-pkgrel, how pacman sees the version of a package
+    pub fn pkg_version(&self) -> PkgVersion {
+        PkgVersion::new(
+            self.variables
+                .get("epoch")
+                .and_then(|values| values.first())
+                .cloned(),
+            self.version.clone().unwrap_or_default(),
+            self.pkgrel(),
+        )
+    }
+
+    pub fn path(&self) -> &Option<PathBuf> {
+        &self.path
+    }
+
+    pub fn pkgbase(&self) -> &String {
+        &self.pkgbase
+    }
+
+    pub fn pkgnames(&self) -> &Vec<String> {
+        &self.pkgnames
+    }
+
+    pub fn set_hash(
+        &mut self,
+        alg: HashAlg,
+        arch: &Option<String>,
+        index: usize,
+        new_hash: String,
+    ) -> Result<Vec<Edit>, Box<dyn Error>> {
+        let variable = arch_variable(&format!("{}sums", alg.name()), arch);
+        if self
+            .variables
+            .get(&variable)
+            .and_then(|hashes| hashes.get(index))
+            == Some(&String::from("SKIP"))
+        {
+            return Ok(Vec::new());
+        }
+        let edit = self.edit(&variable, index, &new_hash)?;
+        if let Some(hashes) = self
+            .hashsums
+            .iter_mut()
+            .find(|hashsums| hashsums.alg == alg)
+            .and_then(|hashsums| hashsums.hashes.get_mut(arch))
+        {
+            hashes[index] = new_hash;
+        }
+        Ok(vec![edit])
+    }
+
+    // Replaces a single value of a top level variable, leaving the rest of the file untouched
+    fn edit(&mut self, variable: &str, index: usize, value: &str) -> Result<Edit, Box<dyn Error>> {
+        let words = self.script.words(variable);
+        let word = match words.get(index) {
+            Some(word) => word,
+            None => {
+                return Err(
+                    format!("{} doesn't have a value with index {}", variable, index).into(),
+                )
+            }
+        };
+        if word.expanded {
+            return Err(format!(
+                "{} comes from brace expansion, unable to change it",
+                variable
+            )
+            .into());
+        }
+        if word.literal().is_none() {
+            return Err(format!(
+                "{} is computed from other variables, unable to change it",
+                variable
+            )
+            .into());
+        }
+        let (raw, edit) = editor::replace(&self.raw, word, value);
+        self.script = parser::parse(&raw)?;
+        self.variables = evaluate(&self.script);
+        self.raw = raw;
+        Ok(edit)
+    }
+
+    fn parse_hashsums(variables: &HashMap<String, Vec<String>>) -> Vec<HashSums> {
+        let mut hashsums = Vec::new();
+        let hash_types = ["md5", "b2", "sha1", "sha224", "sha256", "sha384", "sha512"];
+        for hash_type in hash_types.iter() {
+            let variable = format!("{}sums", hash_type);
+            let mut hashes = HashMap::new();
+            for arch in std::iter::once(None).chain(arches(variables).into_iter().map(Some)) {
+                if let Some(values) = variables.get(&arch_variable(&variable, &arch)) {
+                    hashes.insert(arch, values.clone());
+                }
+            }
+            if !hashes.is_empty() {
+                hashsums.extend(HashSums::new(hash_type.to_string(), hashes));
+            }
+        }
+        hashsums
+    }
+
+    pub fn to_file(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let path = Path::new(path);
+        let display = path.display();
+
+        let mut file = match File::create(path) {
+            Err(why) => panic!("couldn't open {}: {}", display, why),
+            Ok(file) => file,
+        };
+
+        match file.write_all(self.raw.as_bytes()) {
+            Err(why) => Err(Box::new(why)),
+            Ok(_) => Ok(()),
+        }
+    }
+
+    // All values of a top level variable
+    pub fn variable(&self, name: &str) -> Option<&Vec<String>> {
+        self.variables.get(name)
+    }
+
+    // Variables a `package_<pkgname>()` function sets for its package of a split PKGBUILD
+    pub fn package_variables(&self, pkgname: &str) -> HashMap<String, Vec<String>> {
+        let function = match self.script.function(&format!("package_{}", pkgname)) {
+            Some(function) => function,
+            None => return HashMap::new(),
+        };
+        let mut variables = evaluate_assignments(function.assignments(), self.variables.clone());
+        variables.retain(|name, _| function.assignments().any(|a| &a.name == name));
+        variables
+    }
+
+    // Whether a variable, of a package function if given, holds text we didn't evaluate
+    pub fn is_opaque(&self, name: &str, pkgname: Option<&str>) -> bool {
+        let mut opaque = opaque_variables(self.script.assignments(), HashSet::new());
+        if let Some(function) =
+            pkgname.and_then(|pkgname| self.script.function(&format!("package_{}", pkgname)))
+        {
+            opaque = opaque_variables(function.assignments(), opaque);
+        }
+        opaque.contains(name)
+    }
+
+    // Gets a value of a variable
+    pub fn render(&self, variable: String) -> Option<String> {
+        self.variables
+            .get(&variable)
+            .and_then(|values| values.first())
+            .cloned()
+    }
+
+    #[allow(dead_code)]
+    pub fn sources(&self) -> &Vec<Source> {
+        &self.sources
+    }
+
+    fn set_sources(&mut self, sources: Vec<Source>) {
+        self.sources = sources;
+    }
+
+    // Attaches configured upstreams to sources they're meant for
+    pub fn set_upstreams(&mut self, upstreams: &[Upstream]) -> Result<(), Box<dyn Error>> {
+        for upstream in upstreams {
+            let source = self
+                .sources
+                .iter_mut()
+                .find(|source| source.index == upstream.source && source.arch == upstream.arch);
+            match source {
+                Some(source) => source.set_upstream(upstream.clone()),
+                None => {
+                    return Err(format!(
+                        "Upstream is configured for {}[{}] which doesn't exist",
+                        arch_variable("source", &upstream.arch),
+                        upstream.source
+                    )
+                    .into())
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_sources(pkgb: &Rc<RefCell<Pkgbuild>>) -> Vec<Source> {
+        let pkgbuild = pkgb.borrow();
+        let mut sources = Vec::new();
+        for arch in std::iter::once(None).chain(arches(&pkgbuild.variables).into_iter().map(Some)) {
+            let words = pkgbuild.script.words(&arch_variable("source", &arch));
+            for (index, word) in words.iter().enumerate() {
+                sources.push(Source::new(
+                    word.template(),
+                    Rc::downgrade(pkgb),
+                    arch.clone(),
+                    index,
+                ));
+            }
+        }
+        sources
+    }
+
+    // Returns updates if they're newer than current version. pkgver is shared, so every
+    // checked source is updated to the lowest of the latest versions they report.
+    pub fn check_for_updates(&self, settings: &Settings) -> Result<Vec<Update>, Box<dyn Error>> {
+        let mut updates: Vec<(&Source, Update)> = Vec::new();
+        let mut behind: Vec<&Source> = Vec::new();
+        for source in &self.sources {
+            match source.update_available(settings)? {
+                Some(update) => updates.push((source, update)),
+                None if source.is_checked() => behind.push(source),
+                None => {}
+            }
+        }
+        let target = match updates
+            .iter()
+            .map(|(_, update)| &update.version)
+            .min_by(|a, b| vercmp(a, b))
+        {
+            Some(target) => target.clone(),
+            None => return Ok(Vec::new()),
+        };
+        let name = |source: &Source| {
+            format!(
+                "{}[{}]",
+                arch_variable("source", &source.arch),
+                source.index
+            )
+        };
+        if let Some(source) = behind.first() {
+            return Err(format!(
+                "{} has nothing newer than {}, unable to update to {}",
+                name(source),
+                self.version.clone().unwrap_or_default(),
+                target
+            )
+            .into());
+        }
+
+        updates
+            .into_iter()
+            .map(|(source, update)| {
+                if update.version == target {
+                    return Ok(update);
+                }
+                // Only a file at the source's own URL can be taken at an older version
+                if update.url != source.render(&source.url(), &update.version) {
+                    return Err(format!(
+                        "{} is at {} while other sources are at {}",
+                        name(source),
+                        update.version,
+                        target
+                    )
+                    .into());
+                }
+                Ok(Update {
+                    url: source.render(&source.url(), &target),
+                    version: target.clone(),
+                    // Published one is for the newer file
+                    checksum: None,
+                    ..update
+                })
+            })
+            .collect()
+    }
+}
+
+// Architectures with their own sources, `source_x86_64=()` and alike
+fn arches(variables: &HashMap<String, Vec<String>>) -> Vec<String> {
+    variables
+        .get("arch")
+        .map(|arches| {
+            arches
+                .iter()
+                .filter(|arch| variables.contains_key(&format!("source_{}", arch)))
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Name of an array for a given arch, `sha256sums` becomes `sha256sums_aarch64`
+fn arch_variable(name: &str, arch: &Option<String>) -> String {
+    match arch {
+        Some(arch) => format!("{}_{}", name, arch),
+        None => name.to_string(),
+    }
+}
+
+// Values of top level variables, expanded in the order they're assigned
+fn evaluate(script: &Script) -> HashMap<String, Vec<String>> {
+    evaluate_assignments(script.assignments(), HashMap::new())
+}
+
+fn evaluate_assignments<'a>(
+    assignments: impl Iterator<Item = &'a Assignment>,
+    mut variables: HashMap<String, Vec<String>>,
+) -> HashMap<String, Vec<String>> {
+    for assignment in assignments {
+        let values: Vec<String> = assignment
+            .value
+            .words()
+            .iter()
+            .map(|word| word.expand(&|name| variables.get(name).and_then(|v| v.first()).cloned()))
+            .collect();
+        let variable = variables.entry(assignment.name.clone()).or_default();
+        match (&assignment.value, assignment.append) {
+            (Value::Scalar(_), true) => match variable.first_mut() {
+                Some(first) => first.push_str(&values[0]),
+                None => *variable = values,
+            },
+            (Value::Array(_), true) => variable.extend(values),
+            (_, false) => *variable = values,
+        }
+    }
+    variables
+}
+
+// Variables which values depend on `$(...)`, `${name%.*}` and alike, in assignment order
+fn opaque_variables<'a>(
+    assignments: impl Iterator<Item = &'a Assignment>,
+    mut opaque: HashSet<String>,
+) -> HashSet<String> {
+    for assignment in assignments {
+        let words = assignment.value.words();
+        let is_opaque = words
+            .iter()
+            .any(|word| word.is_opaque(&|name| opaque.contains(name)));
+        if is_opaque {
+            opaque.insert(assignment.name.clone());
+        } else if !assignment.append {
+            opaque.remove(&assignment.name);
+        }
+    }
+    opaque
+}
+
+#[allow(dead_code)]
+pub fn dir(path_str: &str) -> &Path {
+    let path = Path::new(path_str);
+    if path.is_file() {
+        return path.parent().unwrap();
+    }
+    path
+}
+
+// Builds a PKGBUILD and returns paths of all the packages it produced
+pub fn build(pkgbuild_dir: &Path, settings: &Settings) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    match settings.build_type() {
+        Build::Chroot => {
+            match settings.chroot() {
+                Some(chroot_path) => {
+                    println!(
+                        "Starting build for \"{}\" in \"{}\"",
+                        &pkgbuild_dir.display(),
+                        &chroot_path.display()
+                    );
+                    // change string into a path & check it
+                    let mut chroot_path = chroot_path.to_str().unwrap().to_string();
+                    if chroot_path.contains('~') {
+                        chroot_path = shellexpand::tilde(&chroot_path).into_owned();
+                    }
+                    //makechrootpkg -c -r ~/hobby/chroot -n -C -T
+                    let mkchrtpkg = Command::new("makechrootpkg")
+                        .current_dir(pkgbuild_dir.to_str().unwrap())
+                        .arg("-c") // Clean the chroot before building
+                        .arg("-r") // The chroot dir to use
+                        .arg(chroot_path)
+                        //.arg(-n) // Run namcap on the package
+                        //.arg(-C) // Run checkpkg on the package
+                        .arg("-T") // Build in a temporary directory
+                        .stdout(Stdio::inherit())
+                        .output()
+                        .expect("failed to execute process");
+                    println!("::group::Building package in chroot");
+                    println!("{}", str::from_utf8(&mkchrtpkg.stdout).unwrap());
+                    println!("{}", str::from_utf8(&mkchrtpkg.stderr).unwrap());
+                    println!("::endgroup::");
+                }
+                None => {
+                    println!("No chroot path");
+                    return Ok(Vec::new());
+                }
+            }
+        }
+        Build::Local => {
+            let mkpkg = Command::new("makepkg")
+                .current_dir(pkgbuild_dir.to_str().unwrap())
+                .arg("--syncdeps") // install dependencies
+                .arg("--cleanbuild") // remove `srcdir` dir before the build
+                .arg("--clean") // clean up after the build
+                .arg("--force") // allows to build package even with existing one in PKGDEST
+                .arg("--needed") // pass to pacman
+                .arg("--noconfirm") // pass to pacman
+                .output()
+                .expect("failed to execute process");
+            println!("::group::Building package locally");
+            println!("{}", str::from_utf8(&mkpkg.stdout).unwrap());
+            println!("{}", str::from_utf8(&mkpkg.stderr).unwrap());
+            println!("::endgroup::");
+        }
+        _ => {
+            println!("We don't support this build method, yet. Sorry!");
+            return Ok(Vec::new());
+        }
+    }
+
+    let packages = package_files(pkgbuild_dir)?;
+    let missing: Vec<String> = packages
+        .iter()
+        .filter(|package| !package.exists())
+        .map(|package| package.display().to_string())
+        .collect();
+    if !missing.is_empty() {
+        let error: Box<dyn std::error::Error> =
+            format!("Build didn't produce: {}", missing.join(", ")).into();
+        return Err(error);
+    }
+    Ok(packages)
+}
+
+// Lists package files a PKGBUILD produces, one per pkgname for split packages
+pub fn package_files(pkgbuild_dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mkpkg = Command::new("makepkg")
+        .current_dir(pkgbuild_dir.to_str().unwrap())
+        .arg("--packagelist")
+        .output()
+        .expect("failed to start `makepkg` process for package list");
+    if mkpkg.status.success() {
+        return Ok(str::from_utf8(&mkpkg.stdout)?
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(PathBuf::from)
+            .collect());
+    }
+
+    let error: Box<dyn std::error::Error> = format!(
+        "Unable to list package files:\n {}",
+        str::from_utf8(&mkpkg.stderr).unwrap()
+    )
+    .into();
+    Err(error)
+}
+
+pub fn update_build_env(settings: Settings) -> Result<(), Box<dyn Error>> {
+    match settings.build_type() {
+        Build::Chroot => match settings.chroot() {
+            Some(chroot_path) => chroot::update(chroot_path),
+            None => {
+                let error: Box<dyn std::error::Error> =
+                    String::from("The chroot path is not specified").into();
+                Err(error)
+            }
+        },
+        Build::Local => {
+            let mkpkg = Command::new("sudo")
+                .arg("pacman")
+                .arg("-Syu")
+                .arg("--noprogressbar")
+                .arg("--noconfirm")
+                .output()
+                .expect("failed to execute process");
+            println!("::group::Updating build environment");
+            println!("{}", str::from_utf8(&mkpkg.stdout).unwrap());
+            println!("{}", str::from_utf8(&mkpkg.stderr).unwrap());
+            println!("::endgroup::");
+            Ok(())
+        }
+        _ => {
+            let error: Box<dyn std::error::Error> = String::from("Unsupported build method").into();
+            Err(error)
+        }
+    }
+}
+
+// take PKGBUILD path and writes .SRCINFO next to it, no makepkg involved
+pub fn srcinfo(pkgbuild_path: &Path) -> Result<(), Box<dyn Error>> {
+    let pkgbuild = Pkgbuild::from_file(pkgbuild_path.to_str().unwrap())?;
+    let srcinfo = Srcinfo::from_pkgbuild(&pkgbuild.borrow());
+    let mut file = File::create(srcinfo_path(pkgbuild_path)?)?;
+    file.write_all(srcinfo.to_string().as_bytes())?;
+    Ok(())
+}
+
+pub fn srcinfo_path(pkgbuild_path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let pkgbuild_dir = pkgbuild_path.parent().unwrap();
+    Ok(pkgbuild_dir.join(".SRCINFO"))
+}
+
+pub fn find_variables(string: String) -> Vec<String> {
+    let re = Regex::new(r"\$\{.*?\}").unwrap();
+    re.captures_iter(&string)
+        .map(|var_capture| {
+            var_capture
+                .get(0)
+                .unwrap()
+                .as_str()
+                .to_string()
+                .replace("${", "")
+                .replace("}", "")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn brace_expanded_sources_line_up_with_checksums() {
+        let pkgbuild = Pkgbuild::new(
+            String::from(
+                "pkgname=foo\npkgver=1\npkgrel=1\narch=(any)\nsource=(\"https://e.org/foo-$pkgver.tar.gz\"{,.sig} \"https://e.org/bar-$pkgver.tar.gz\")\nsha256sums=('aa' 'SKIP' 'bb')\n",
+            ),
+            None,
+        )
+        .unwrap();
+        let pkgbuild = pkgbuild.borrow();
+        let templates: Vec<String> = pkgbuild
+            .sources()
+            .iter()
+            .map(|source| source.template())
+            .collect();
+        assert_eq!(
+            templates,
+            [
+                "https://e.org/foo-${pkgver}.tar.gz",
+                "https://e.org/foo-${pkgver}.tar.gz.sig",
+                "https://e.org/bar-${pkgver}.tar.gz",
+            ]
+        );
+        assert!(!pkgbuild.is_skipped(&None, 0));
+        assert!(pkgbuild.is_skipped(&None, 1));
+        assert!(!pkgbuild.is_skipped(&None, 2));
+        assert_eq!(pkgbuild.hash_algs(&None, 2).unwrap(), [HashAlg::SHA256]);
+    }
+
+    #[test]
+    fn checksums_of_brace_expanded_words_arent_edited() {
+        let pkgbuild = Pkgbuild::new(
+            String::from("pkgname=foo\npkgver=1\npkgrel=1\nsource=(a b)\nsha256sums=({aa,bb})\n"),
+            None,
+        )
+        .unwrap();
+        let mut pkgbuild = pkgbuild.borrow_mut();
+        assert!(pkgbuild
+            .set_hash(HashAlg::SHA256, &None, 1, String::from("cc"))
+            .is_err());
+    }
+}
@@ -0,0 +1,1121 @@
+// Parser for the subset of bash PKGBUILD files are written in.
+//
+// Nothing gets evaluated here. The parser only builds a syntax tree of top level
+// assignments and functions, every node keeps a byte span into the original text
+// so values can be read (and later rewritten) without losing any formatting.
+// Everything else (commands, conditionals, loops) is skipped over as a whole.
+
+use std::error::Error;
+use std::ops::Range;
+
+pub type Span = Range<usize>;
+
+#[derive(Clone, Debug)]
+pub struct Script {
+    pub statements: Vec<Statement>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Statement {
+    Assignment(Assignment),
+    Function(Function),
+    // Any other command, simple or compound
+    Command,
+}
+
+#[derive(Clone, Debug)]
+pub struct Assignment {
+    pub name: String,
+    // `name+=value` instead of `name=value`
+    pub append: bool,
+    pub value: Value,
+}
+
+#[derive(Clone, Debug)]
+pub enum Value {
+    Scalar(Word),
+    Array(Vec<Word>),
+}
+
+#[derive(Clone, Debug)]
+pub struct Function {
+    pub name: String,
+    pub body: Vec<Statement>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Word {
+    pub parts: Vec<Part>,
+    // Raw word including quotes, empty for `name=`
+    pub span: Span,
+    // One of the words brace expansion made of the span, `a{,.sig}` makes two
+    pub expanded: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Part {
+    // Text with quotes and escapes already removed
+    Literal(String),
+    // `$name` or `${name}`
+    Variable(String),
+    // Expansions we don't evaluate: `$(...)`, `${name%.*}`, `$1`, backticks...
+    Opaque(String),
+}
+
+impl Script {
+    pub fn assignments(&self) -> impl Iterator<Item = &Assignment> {
+        assignments(&self.statements)
+    }
+
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.statements
+            .iter()
+            .rev()
+            .find_map(|statement| match statement {
+                Statement::Function(function) if function.name == name => Some(function),
+                _ => None,
+            })
+    }
+
+    // Words a top level variable ends up holding, `+=` included
+    pub fn words(&self, name: &str) -> Vec<&Word> {
+        words(&self.statements, name)
+    }
+}
+
+impl Function {
+    pub fn assignments(&self) -> impl Iterator<Item = &Assignment> {
+        assignments(&self.body)
+    }
+}
+
+fn assignments(statements: &[Statement]) -> impl Iterator<Item = &Assignment> {
+    statements.iter().filter_map(|statement| match statement {
+        Statement::Assignment(assignment) => Some(assignment),
+        _ => None,
+    })
+}
+
+fn words<'a>(statements: &'a [Statement], name: &str) -> Vec<&'a Word> {
+    let mut words = Vec::new();
+    for assignment in assignments(statements).filter(|a| a.name == name) {
+        if !assignment.append {
+            words.clear();
+        }
+        words.extend(assignment.value.words());
+    }
+    words
+}
+
+impl Value {
+    pub fn words(&self) -> Vec<&Word> {
+        match self {
+            Value::Scalar(word) => vec![word],
+            Value::Array(words) => words.iter().collect(),
+        }
+    }
+}
+
+impl Word {
+    // Value of a word, if it doesn't depend on any expansion
+    pub fn literal(&self) -> Option<String> {
+        let mut literal = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => literal.push_str(text),
+                _ => return None,
+            }
+        }
+        Some(literal)
+    }
+
+    // Unquoted word with variables normalized to `${name}`
+    pub fn template(&self) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(text) | Part::Opaque(text) => text.clone(),
+                Part::Variable(name) => format!("${{{}}}", name),
+            })
+            .collect()
+    }
+
+    // Whether the value comes from an expansion we don't evaluate, directly or through a variable
+    pub fn is_opaque(&self, opaque: &dyn Fn(&str) -> bool) -> bool {
+        self.parts.iter().any(|part| match part {
+            Part::Opaque(_) => true,
+            Part::Variable(name) => opaque(name),
+            Part::Literal(_) => false,
+        })
+    }
+
+    // Substitutes variables, unknown ones expand to nothing like in bash
+    pub fn expand(&self, lookup: &dyn Fn(&str) -> Option<String>) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(text) | Part::Opaque(text) => text.clone(),
+                Part::Variable(name) => lookup(name).unwrap_or_default(),
+            })
+            .collect()
+    }
+}
+
+pub fn parse(src: &str) -> Result<Script, Box<dyn Error>> {
+    let mut parser = Parser::new(src);
+    let statements = parser.statements(false)?;
+    Ok(Script { statements })
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    // Delimiters of here-documents which bodies start after the next newline
+    heredocs: Vec<(String, bool)>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Parser<'a> {
+        Parser {
+            src,
+            pos: 0,
+            heredocs: Vec::new(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn peek_byte(&self, offset: usize) -> Option<u8> {
+        self.src.as_bytes().get(self.pos + offset).copied()
+    }
+
+    fn starts_with(&self, pattern: &str) -> bool {
+        self.src[self.pos..].starts_with(pattern)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn error(&self, message: &str) -> Box<dyn Error> {
+        let line = self.src[..self.pos].matches('\n').count() + 1;
+        format!("Unable to parse PKGBUILD, line {}: {}", line, message).into()
+    }
+
+    // Checks for a reserved word, like `}` or `function`, at the current position
+    fn reserved(&self, word: &str) -> bool {
+        self.starts_with(word)
+            && self.src[self.pos + word.len()..]
+                .chars()
+                .next()
+                .is_none_or(|c| c.is_whitespace() || is_operator(c))
+    }
+
+    fn statements(&mut self, in_function: bool) -> Result<Vec<Statement>, Box<dyn Error>> {
+        let mut statements = Vec::new();
+        loop {
+            self.skip_separators()?;
+            if self.peek().is_none() {
+                if in_function {
+                    return Err(self.error("function body is never closed"));
+                }
+                return Ok(statements);
+            }
+            if in_function && self.reserved("}") {
+                self.pos += 1;
+                return Ok(statements);
+            }
+            statements.push(self.statement()?);
+        }
+    }
+
+    fn statement(&mut self) -> Result<Statement, Box<dyn Error>> {
+        let start = self.pos;
+        if let Some(assignment) = self.assignment()? {
+            self.skip_spaces();
+            if self.at_command_end() || self.at_assignment() {
+                return Ok(Statement::Assignment(assignment));
+            }
+            // `name=value command` only sets the variable for the command
+            self.command()?;
+            return Ok(Statement::Command);
+        }
+        if let Some(function) = self.function()? {
+            return Ok(Statement::Function(function));
+        }
+        self.command()?;
+        if self.pos == start {
+            return Err(self.error(&format!("unexpected '{}'", self.peek().unwrap())));
+        }
+        Ok(Statement::Command)
+    }
+
+    fn at_command_end(&self) -> bool {
+        match self.peek() {
+            None => true,
+            Some(c) => "\n;#&|)".contains(c),
+        }
+    }
+
+    fn at_assignment(&self) -> bool {
+        let name_len = name_len(&self.src[self.pos..]);
+        name_len > 0 && {
+            let rest = &self.src[self.pos + name_len..];
+            rest.starts_with('=') || rest.starts_with("+=")
+        }
+    }
+
+    fn assignment(&mut self) -> Result<Option<Assignment>, Box<dyn Error>> {
+        let start = self.pos;
+        let name_len = name_len(&self.src[start..]);
+        if name_len == 0 {
+            return Ok(None);
+        }
+        let rest = &self.src[start + name_len..];
+        let (append, operator_len) = if rest.starts_with("+=") {
+            (true, 2)
+        } else if rest.starts_with('=') {
+            (false, 1)
+        } else {
+            return Ok(None);
+        };
+        self.pos = start + name_len + operator_len;
+        let value = if self.peek() == Some('(') {
+            self.pos += 1;
+            Value::Array(self.array()?)
+        } else {
+            Value::Scalar(self.word()?.unwrap_or(Word {
+                parts: Vec::new(),
+                span: self.pos..self.pos,
+                expanded: false,
+            }))
+        };
+        Ok(Some(Assignment {
+            name: self.src[start..start + name_len].to_string(),
+            append,
+            value,
+        }))
+    }
+
+    fn array(&mut self) -> Result<Vec<Word>, Box<dyn Error>> {
+        let mut words = Vec::new();
+        loop {
+            self.skip_spaces();
+            match self.peek() {
+                None => return Err(self.error("array is never closed")),
+                Some('\n') => self.newline()?,
+                Some('#') => self.skip_comment(),
+                Some(')') => {
+                    self.pos += 1;
+                    return Ok(words);
+                }
+                Some(c) => match self.word()? {
+                    Some(word) => words.append(&mut self.expand_braces(word)?),
+                    None => return Err(self.error(&format!("unexpected '{}' in array", c))),
+                },
+            }
+        }
+    }
+
+    // Matches `name() {` and `function name {`
+    fn function(&mut self) -> Result<Option<Function>, Box<dyn Error>> {
+        let start = self.pos;
+        let keyword = self.reserved("function");
+        if keyword {
+            self.pos += "function".len();
+            self.skip_spaces();
+        }
+        let name_len = self.src[self.pos..]
+            .chars()
+            .take_while(|c| !c.is_whitespace() && !is_operator(*c) && !"'\"`$\\{}=".contains(*c))
+            .map(char::len_utf8)
+            .sum::<usize>();
+        if name_len == 0 {
+            self.pos = start;
+            return Ok(None);
+        }
+        let name = self.src[self.pos..self.pos + name_len].to_string();
+        self.pos += name_len;
+        self.skip_spaces();
+        if self.starts_with("(") {
+            self.pos += 1;
+            self.skip_spaces();
+            if !self.starts_with(")") {
+                self.pos = start;
+                return Ok(None);
+            }
+            self.pos += 1;
+        } else if !keyword {
+            self.pos = start;
+            return Ok(None);
+        }
+        loop {
+            self.skip_spaces();
+            match self.peek() {
+                Some('\n') => self.newline()?,
+                Some('#') => self.skip_comment(),
+                _ => break,
+            }
+        }
+        if !self.reserved("{") {
+            return Err(self.error(&format!("function {} has no body in braces", name)));
+        }
+        self.pos += 1;
+        let body = self.statements(true)?;
+        Ok(Some(Function { name, body }))
+    }
+
+    // Consumes a whole command, compound ones included, up to its separator
+    fn command(&mut self) -> Result<(), Box<dyn Error>> {
+        // Reserved words closing the compound commands we're inside of
+        let mut closers: Vec<&'static str> = Vec::new();
+        let mut command_position = true;
+        let mut continuation = false;
+        let mut case_header = false;
+        let mut case_pattern = false;
+        loop {
+            self.skip_spaces();
+            let c = match self.peek() {
+                Some(c) => c,
+                None => match closers.last() {
+                    Some(closer) => return Err(self.error(&format!("expected '{}'", closer))),
+                    None => return Ok(()),
+                },
+            };
+            match c {
+                '\n' => {
+                    if closers.is_empty() && !continuation {
+                        return Ok(());
+                    }
+                    self.newline()?;
+                    command_position = !case_pattern;
+                }
+                '#' => self.skip_comment(),
+                ';' => {
+                    if closers.last() == Some(&"esac") && !case_pattern {
+                        if self.starts_with(";;&") {
+                            self.pos += 3;
+                            case_pattern = true;
+                            continue;
+                        } else if self.starts_with(";;") || self.starts_with(";&") {
+                            self.pos += 2;
+                            case_pattern = true;
+                            continue;
+                        }
+                    }
+                    if closers.is_empty() {
+                        return Ok(());
+                    }
+                    self.pos += 1;
+                    command_position = true;
+                }
+                '&' | '|' => {
+                    if ["&&", "||", "|&", "&>"]
+                        .iter()
+                        .any(|op| self.starts_with(op))
+                    {
+                        self.pos += 2;
+                    } else {
+                        self.pos += 1;
+                        if c == '&' && closers.is_empty() {
+                            return Ok(());
+                        }
+                    }
+                    if !case_pattern {
+                        command_position = true;
+                        continuation = true;
+                    }
+                }
+                '<' | '>' => {
+                    self.redirection()?;
+                    continuation = false;
+                }
+                '(' => {
+                    continuation = false;
+                    if case_pattern {
+                        self.pos += 1;
+                    } else if command_position && self.starts_with("((") {
+                        self.skip_balanced('(', ')')?;
+                        command_position = false;
+                    } else {
+                        self.pos += 1;
+                        closers.push(")");
+                        command_position = true;
+                    }
+                }
+                ')' => {
+                    continuation = false;
+                    if case_pattern {
+                        self.pos += 1;
+                        case_pattern = false;
+                        command_position = true;
+                    } else if closers.last() == Some(&")") {
+                        self.pos += 1;
+                        closers.pop();
+                        command_position = false;
+                    } else if closers.is_empty() {
+                        return Ok(());
+                    } else {
+                        return Err(self.error("unexpected ')'"));
+                    }
+                }
+                _ => {
+                    let word_start = self.pos;
+                    self.word()?;
+                    continuation = false;
+                    let text = &self.src[word_start..self.pos];
+                    if case_pattern {
+                        if text == "esac" {
+                            closers.pop();
+                            case_pattern = false;
+                            command_position = false;
+                        }
+                    } else if command_position {
+                        match text {
+                            "if" => closers.push("fi"),
+                            // Loop headers are separated from `do` by `;` or a newline
+                            "for" | "select" => {
+                                closers.push("do");
+                                command_position = false;
+                            }
+                            "while" | "until" => closers.push("do"),
+                            "do" => {
+                                if closers.last() == Some(&"do") {
+                                    closers.pop();
+                                }
+                                closers.push("done");
+                            }
+                            "{" => closers.push("}"),
+                            "[[" => {
+                                closers.push("]]");
+                                command_position = false;
+                            }
+                            "case" => {
+                                closers.push("esac");
+                                case_header = true;
+                                command_position = false;
+                            }
+                            "fi" | "done" | "esac" | "}" => {
+                                if closers.last() == Some(&text) {
+                                    closers.pop();
+                                    command_position = false;
+                                } else if text == "}" && closers.is_empty() {
+                                    // Closes the function we're in
+                                    self.pos = word_start;
+                                    return Ok(());
+                                } else {
+                                    return Err(self.error(&format!("unexpected '{}'", text)));
+                                }
+                            }
+                            "then" | "else" | "elif" | "!" | "time" => {}
+                            _ => command_position = name_len(text) > 0 && is_assignment(text),
+                        }
+                    } else if text == "]]" && closers.last() == Some(&"]]") {
+                        closers.pop();
+                    } else if case_header && text == "in" {
+                        case_header = false;
+                        case_pattern = true;
+                    }
+                }
+            }
+        }
+    }
+
+    fn redirection(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.starts_with("<<<") {
+            self.pos += 3;
+        } else if self.starts_with("<<") {
+            self.pos += 2;
+            let strip_tabs = self.peek() == Some('-');
+            if strip_tabs {
+                self.pos += 1;
+            }
+            self.skip_spaces();
+            let start = self.pos;
+            if self.word()?.is_none() {
+                return Err(self.error("here-document without a delimiter"));
+            }
+            let delimiter: String = self.src[start..self.pos]
+                .chars()
+                .filter(|c| !"'\"\\".contains(*c))
+                .collect();
+            self.heredocs.push((delimiter, strip_tabs));
+        } else if self.peek_byte(1) == Some(b'(') {
+            // Process substitution
+            self.pos += 1;
+            self.skip_balanced('(', ')')?;
+        } else {
+            self.pos += 1;
+            if let Some(b'>' | b'&' | b'|') = self.peek_byte(0) {
+                self.pos += 1;
+            }
+        }
+        Ok(())
+    }
+
+    // Reads a single word, None if there's no word at the current position
+    fn word(&mut self) -> Result<Option<Word>, Box<dyn Error>> {
+        let start = self.pos;
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        while let Some(c) = self.peek() {
+            match c {
+                c if c == ' ' || c == '\t' || c == '\n' || is_operator(c) => break,
+                '\\' => {
+                    self.pos += 1;
+                    match self.bump() {
+                        Some('\n') => {}
+                        Some(c) => literal.push(c),
+                        None => literal.push('\\'),
+                    }
+                }
+                '\'' => {
+                    self.pos += 1;
+                    let end = match self.src[self.pos..].find('\'') {
+                        Some(offset) => self.pos + offset,
+                        None => return Err(self.error("unterminated single quote")),
+                    };
+                    literal.push_str(&self.src[self.pos..end]);
+                    self.pos = end + 1;
+                }
+                '"' => {
+                    self.pos += 1;
+                    self.double_quoted(&mut parts, &mut literal)?;
+                }
+                '$' => self.dollar(&mut parts, &mut literal, false)?,
+                '`' => {
+                    let start = self.pos;
+                    self.skip_backticks()?;
+                    flush(&mut parts, &mut literal);
+                    parts.push(Part::Opaque(self.src[start..self.pos].to_string()));
+                }
+                c => {
+                    literal.push(c);
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+        flush(&mut parts, &mut literal);
+        if self.pos == start {
+            return Ok(None);
+        }
+        Ok(Some(Word {
+            parts,
+            span: start..self.pos,
+            expanded: false,
+        }))
+    }
+
+    // Array elements go through brace expansion, scalar values don't
+    fn expand_braces(&self, word: Word) -> Result<Vec<Word>, Box<dyn Error>> {
+        let raw = &self.src[word.span.clone()];
+        let alternatives = match expand_braces(raw) {
+            Ok(alternatives) => alternatives,
+            Err(why) => return Err(self.error(&why.to_string())),
+        };
+        if alternatives.len() == 1 && alternatives[0] == raw {
+            return Ok(vec![word]);
+        }
+        let mut words = Vec::new();
+        for alternative in &alternatives {
+            let mut parser = Parser::new(alternative);
+            // Empty unquoted alternatives, like the first one of `a{,.sig}` without `a`, vanish
+            if let Some(expanded) = parser.word()? {
+                words.push(Word {
+                    parts: expanded.parts,
+                    span: word.span.clone(),
+                    expanded: true,
+                });
+            }
+        }
+        Ok(words)
+    }
+
+    // Skips an escape, a quoted string or an expansion at the current position, if there's one
+    fn skip_quoted(&mut self) -> Result<bool, Box<dyn Error>> {
+        match self.peek() {
+            Some('\\') => {
+                self.pos += 1;
+                self.bump();
+            }
+            Some('\'') => match self.src[self.pos + 1..].find('\'') {
+                Some(offset) => self.pos += offset + 2,
+                None => return Err(self.error("unterminated single quote")),
+            },
+            Some('"') => {
+                self.pos += 1;
+                self.double_quoted(&mut Vec::new(), &mut String::new())?;
+            }
+            Some('`') => self.skip_backticks()?,
+            Some('$') if self.peek_byte(1) == Some(b'{') => {
+                self.pos += 1;
+                self.skip_balanced('{', '}')?;
+            }
+            Some('$') if self.peek_byte(1) == Some(b'(') => {
+                self.pos += 1;
+                self.skip_balanced('(', ')')?;
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    // Positions of top level commas of a `{a,b}` list followed by the one of its closing brace
+    fn brace_list(&mut self) -> Result<Option<Vec<usize>>, Box<dyn Error>> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut depth = 0;
+        let mut bounds = Vec::new();
+        let mut list = None;
+        while let Some(c) = self.peek() {
+            if self.skip_quoted()? {
+                continue;
+            }
+            match c {
+                '}' if depth == 0 => {
+                    bounds.push(self.pos);
+                    list = Some(bounds);
+                    break;
+                }
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                ',' if depth == 0 => bounds.push(self.pos),
+                _ => {}
+            }
+            self.pos += c.len_utf8();
+        }
+        self.pos = start;
+        Ok(list)
+    }
+
+    fn double_quoted(
+        &mut self,
+        parts: &mut Vec<Part>,
+        literal: &mut String,
+    ) -> Result<(), Box<dyn Error>> {
+        loop {
+            match self.peek() {
+                None => return Err(self.error("unterminated double quote")),
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some('\n') => self.pos += 1,
+                        Some(c) if "$`\"\\".contains(c) => {
+                            literal.push(c);
+                            self.pos += 1;
+                        }
+                        _ => literal.push('\\'),
+                    }
+                }
+                Some('$') => self.dollar(parts, literal, true)?,
+                Some('`') => {
+                    let start = self.pos;
+                    self.skip_backticks()?;
+                    flush(parts, literal);
+                    parts.push(Part::Opaque(self.src[start..self.pos].to_string()));
+                }
+                Some(c) => {
+                    literal.push(c);
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+    }
+
+    fn dollar(
+        &mut self,
+        parts: &mut Vec<Part>,
+        literal: &mut String,
+        quoted: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let start = self.pos;
+        self.pos += 1;
+        match self.peek() {
+            Some('{') => {
+                self.skip_balanced('{', '}')?;
+                let inner = &self.src[start + 2..self.pos - 1];
+                flush(parts, literal);
+                if name_len(inner) == inner.len() && !inner.is_empty() {
+                    parts.push(Part::Variable(inner.to_string()));
+                } else {
+                    parts.push(Part::Opaque(self.src[start..self.pos].to_string()));
+                }
+            }
+            Some('(') => {
+                self.skip_balanced('(', ')')?;
+                flush(parts, literal);
+                parts.push(Part::Opaque(self.src[start..self.pos].to_string()));
+            }
+            Some('\'') if !quoted => {
+                self.pos += 1;
+                self.ansi_c_quoted(literal)?;
+            }
+            Some('"') if !quoted => {
+                self.pos += 1;
+                self.double_quoted(parts, literal)?;
+            }
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                let len = name_len(&self.src[self.pos..]);
+                flush(parts, literal);
+                parts.push(Part::Variable(
+                    self.src[self.pos..self.pos + len].to_string(),
+                ));
+                self.pos += len;
+            }
+            Some(c) if c.is_ascii_digit() || "@*#?$!-".contains(c) => {
+                self.pos += 1;
+                flush(parts, literal);
+                parts.push(Part::Opaque(self.src[start..self.pos].to_string()));
+            }
+            _ => literal.push('$'),
+        }
+        Ok(())
+    }
+
+    // $'...' strings
+    fn ansi_c_quoted(&mut self, literal: &mut String) -> Result<(), Box<dyn Error>> {
+        loop {
+            match self.bump() {
+                None => return Err(self.error("unterminated $'' string")),
+                Some('\'') => return Ok(()),
+                Some('\\') => match self.bump() {
+                    Some('n') => literal.push('\n'),
+                    Some('t') => literal.push('\t'),
+                    Some('r') => literal.push('\r'),
+                    Some(c) if "\\'\"".contains(c) => literal.push(c),
+                    Some(c) => {
+                        literal.push('\\');
+                        literal.push(c);
+                    }
+                    None => return Err(self.error("unterminated $'' string")),
+                },
+                Some(c) => literal.push(c),
+            }
+        }
+    }
+
+    // Skips from an opening bracket to the matching closing one, minding quotes
+    fn skip_balanced(&mut self, open: char, close: char) -> Result<(), Box<dyn Error>> {
+        let start = self.pos;
+        let mut depth = 0;
+        loop {
+            match self.bump() {
+                None => {
+                    self.pos = start;
+                    return Err(self.error(&format!("'{}' is never closed", open)));
+                }
+                Some('\\') => {
+                    self.bump();
+                }
+                Some('\'') if open == '(' => match self.src[self.pos..].find('\'') {
+                    Some(offset) => self.pos += offset + 1,
+                    None => return Err(self.error("unterminated single quote")),
+                },
+                Some('"') => {
+                    let mut parts = Vec::new();
+                    self.double_quoted(&mut parts, &mut String::new())?;
+                }
+                Some('`') => {
+                    self.pos -= 1;
+                    self.skip_backticks()?;
+                }
+                Some(c) if c == open => depth += 1,
+                Some(c) if c == close => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    fn skip_backticks(&mut self) -> Result<(), Box<dyn Error>> {
+        self.pos += 1;
+        loop {
+            match self.bump() {
+                None => return Err(self.error("unterminated backtick substitution")),
+                Some('\\') => {
+                    self.bump();
+                }
+                Some('`') => return Ok(()),
+                Some(_) => {}
+            }
+        }
+    }
+
+    // Skips spaces, tabs and line continuations, newlines are significant
+    fn skip_spaces(&mut self) {
+        loop {
+            match self.peek() {
+                Some(' ') | Some('\t') => self.pos += 1,
+                Some('\\') if self.peek_byte(1) == Some(b'\n') => self.pos += 2,
+                _ => return,
+            }
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        match self.src[self.pos..].find('\n') {
+            Some(offset) => self.pos += offset,
+            None => self.pos = self.src.len(),
+        }
+    }
+
+    // Skips whitespace, separators and comments between statements
+    fn skip_separators(&mut self) -> Result<(), Box<dyn Error>> {
+        loop {
+            self.skip_spaces();
+            match self.peek() {
+                Some('\n') => self.newline()?,
+                Some(';') => self.pos += 1,
+                Some('#') => self.skip_comment(),
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    // Consumes a newline and bodies of here-documents which start after it
+    fn newline(&mut self) -> Result<(), Box<dyn Error>> {
+        self.pos += 1;
+        for (delimiter, strip_tabs) in std::mem::take(&mut self.heredocs) {
+            while self.pos < self.src.len() {
+                let end = self.src[self.pos..]
+                    .find('\n')
+                    .map_or(self.src.len(), |offset| self.pos + offset);
+                let mut line = &self.src[self.pos..end];
+                if strip_tabs {
+                    line = line.trim_start_matches('\t');
+                }
+                self.pos = (end + 1).min(self.src.len());
+                if line == delimiter {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Raw words brace expansion makes of a raw word, `a{,.sig}` becomes `a` and `a.sig`
+fn expand_braces(raw: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut parser = Parser::new(raw);
+    while let Some(c) = parser.peek() {
+        if parser.skip_quoted()? {
+            continue;
+        }
+        if c == '{' {
+            if let Some(list) = parser.brace_list()? {
+                let start = parser.pos;
+                let end = *list.last().unwrap();
+                if list.len() > 1 {
+                    let bounds: Vec<usize> = std::iter::once(start).chain(list).collect();
+                    let mut expanded = Vec::new();
+                    for bound in bounds.windows(2) {
+                        expanded.append(&mut expand_braces(&format!(
+                            "{}{}{}",
+                            &raw[..start],
+                            &raw[bound[0] + 1..bound[1]],
+                            &raw[end + 1..]
+                        ))?);
+                    }
+                    return Ok(expanded);
+                }
+                if is_sequence(&raw[start + 1..end]) {
+                    return Err(
+                        format!("brace sequence {} isn't supported", &raw[start..=end]).into(),
+                    );
+                }
+            }
+        }
+        parser.pos += c.len_utf8();
+    }
+    Ok(vec![raw.to_string()])
+}
+
+// `1..5`, `a..e` and `1..10..2`
+fn is_sequence(inner: &str) -> bool {
+    let bounds: Vec<&str> = inner.split("..").collect();
+    (2..=3).contains(&bounds.len())
+        && bounds.iter().all(|bound| {
+            let bound = bound.strip_prefix('-').unwrap_or(bound);
+            !bound.is_empty() && bound.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+}
+
+fn flush(parts: &mut Vec<Part>, literal: &mut String) {
+    if !literal.is_empty() {
+        parts.push(Part::Literal(std::mem::take(literal)));
+    }
+}
+
+fn is_operator(c: char) -> bool {
+    ";&|()<>".contains(c)
+}
+
+// Length of a variable name at the beginning of a string
+fn name_len(s: &str) -> usize {
+    if s.starts_with(|c: char| c.is_ascii_digit()) {
+        return 0;
+    }
+    s.bytes()
+        .take_while(|b| b.is_ascii_alphanumeric() || *b == b'_')
+        .count()
+}
+
+fn is_assignment(word: &str) -> bool {
+    let rest = &word[name_len(word)..];
+    rest.starts_with('=') || rest.starts_with("+=")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literals(script: &Script, name: &str) -> Vec<String> {
+        script
+            .words(name)
+            .iter()
+            .map(|word| word.literal().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn indented_assignments() {
+        let script = parse("  pkgver=1.0\n\tpkgrel=2\n").unwrap();
+        assert_eq!(literals(&script, "pkgver"), ["1.0"]);
+        assert_eq!(literals(&script, "pkgrel"), ["2"]);
+    }
+
+    #[test]
+    fn single_quoted_sources() {
+        let script = parse("source=('https://e.org/$pkgname-1.tar.gz' \"b c\")\n").unwrap();
+        assert_eq!(
+            literals(&script, "source"),
+            ["https://e.org/$pkgname-1.tar.gz", "b c"]
+        );
+    }
+
+    #[test]
+    fn comments_with_parentheses() {
+        let script = parse("source=(a # mirror (slow)\n  b) # done)\npkgrel=1\n").unwrap();
+        assert_eq!(literals(&script, "source"), ["a", "b"]);
+        assert_eq!(literals(&script, "pkgrel"), ["1"]);
+    }
+
+    #[test]
+    fn values_with_equals_sign() {
+        let script = parse("url=https://e.org/?a=b\noptions=(a=b)\n").unwrap();
+        assert_eq!(literals(&script, "url"), ["https://e.org/?a=b"]);
+        assert_eq!(literals(&script, "options"), ["a=b"]);
+    }
+
+    #[test]
+    fn appended_arrays() {
+        let script =
+            parse("depends=(a)\ndepends+=(b)\nmakedepends=(c)\nmakedepends=(d)\n").unwrap();
+        assert_eq!(literals(&script, "depends"), ["a", "b"]);
+        assert_eq!(literals(&script, "makedepends"), ["d"]);
+    }
+
+    #[test]
+    fn heredocs_are_skipped() {
+        let script =
+            parse("cat <<EOF > file\npkgver=9\nEOF\ncat <<-'END'\n\tpkgver=8\n\tEND\npkgver=1\n")
+                .unwrap();
+        assert_eq!(literals(&script, "pkgver"), ["1"]);
+        assert_eq!(
+            script.assignments().filter(|a| a.name == "pkgver").count(),
+            1
+        );
+    }
+
+    #[test]
+    fn functions_with_case_and_substitutions() {
+        let script = parse(
+            "pkgver() {\n  case $CARCH in\n    x86_64) echo \"$(uname -m)\" ;;\n    *) echo ')' ;;\n  esac\n  _v=$(git describe | sed 's/)//')\n}\npkgver=1\n",
+        )
+        .unwrap();
+        assert_eq!(literals(&script, "pkgver"), ["1"]);
+        let function = script.function("pkgver").unwrap();
+        let assignment = function.assignments().next().unwrap();
+        assert_eq!(assignment.name, "_v");
+        match &assignment.value {
+            Value::Scalar(word) => assert!(matches!(word.parts[..], [Part::Opaque(_)])),
+            Value::Array(_) => panic!("_v isn't an array"),
+        }
+    }
+
+    #[test]
+    fn templates_normalize_variables() {
+        let script = parse("source=(\"$pkgname-${pkgver}.tar.gz\")\n").unwrap();
+        assert_eq!(
+            script.words("source")[0].template(),
+            "${pkgname}-${pkgver}.tar.gz"
+        );
+    }
+
+    #[test]
+    fn braces_expand_in_arrays() {
+        let script = parse(
+            "source=(\"https://e.org/foo-$pkgver.tar.gz\"{,.sig} \"https://e.org/bar-$pkgver.tar.gz\")\n",
+        )
+        .unwrap();
+        let templates: Vec<String> = script
+            .words("source")
+            .iter()
+            .map(|word| word.template())
+            .collect();
+        assert_eq!(
+            templates,
+            [
+                "https://e.org/foo-${pkgver}.tar.gz",
+                "https://e.org/foo-${pkgver}.tar.gz.sig",
+                "https://e.org/bar-${pkgver}.tar.gz",
+            ]
+        );
+        assert!(script.words("source")[1].expanded);
+        assert!(!script.words("source")[2].expanded);
+    }
+
+    #[test]
+    fn braces_expand_like_bash() {
+        let script =
+            parse("a=(x{1,2}y{a,b} '{c,d}' {e} ${f:-{g,h}} {i,{j,k}})\nb={l,m}\n").unwrap();
+        assert_eq!(
+            script
+                .words("a")
+                .iter()
+                .map(|word| word.template())
+                .collect::<Vec<String>>(),
+            [
+                "x1ya",
+                "x1yb",
+                "x2ya",
+                "x2yb",
+                "{c,d}",
+                "{e}",
+                "${f:-{g,h}}",
+                "i",
+                "j",
+                "k"
+            ]
+        );
+        assert_eq!(literals(&script, "b"), ["{l,m}"]);
+        assert!(parse("a=(x{1..3})\n").is_err());
+    }
+
+    #[test]
+    fn unclosed_array_is_an_error() {
+        assert!(parse("source=(a b\n").is_err());
+    }
+}
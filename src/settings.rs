@@ -2,9 +2,12 @@ extern crate dirs;
 
 use config::{Config, ConfigError, Environment, File};
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::env;
 use std::path::PathBuf;
 
+use crate::source::upstream::Package;
+
 #[derive(Clone, Deserialize, PartialEq, Debug)]
 pub enum Build {
     Local,
@@ -19,8 +22,25 @@ pub struct Settings {
     chroot: Option<PathBuf>,
     commit: bool,
     commit_message: String,
+    bump_rel_commit_message: String,
     push: bool,
     srcinfo: bool,
+    github_api: String,
+    github_prerelease: bool,
+    github_token: Option<String>,
+    pypi_api: String,
+    crates_api: String,
+    npm_registry: String,
+    apt_suite: String,
+    npm_highest_version: bool,
+    // API base URLs of self-hosted instances by host
+    #[serde(default)]
+    gitlab_api: HashMap<String, String>,
+    #[serde(default)]
+    gitea_api: HashMap<String, String>,
+    // Per package settings by pkgbase
+    #[serde(default)]
+    packages: HashMap<String, Package>,
 }
 
 impl Settings {
@@ -33,8 +53,19 @@ impl Settings {
             "commit_message",
             "${pkgname}: ${old_version} -> ${new_version}",
         )?;
+        s.set_default(
+            "bump_rel_commit_message",
+            "${pkgname}: rebuild ${old_version} -> ${new_version}",
+        )?;
         s.set_default("push", "false")?;
         s.set_default("srcinfo", "false")?;
+        s.set_default("github_api", "https://api.github.com")?;
+        s.set_default("github_prerelease", "false")?;
+        s.set_default("pypi_api", "https://pypi.org")?;
+        s.set_default("crates_api", "https://crates.io")?;
+        s.set_default("npm_registry", "https://registry.npmjs.org")?;
+        s.set_default("npm_highest_version", "false")?;
+        s.set_default("apt_suite", "stable")?;
 
         match file {
             Some(f) => {
@@ -68,6 +99,10 @@ impl Settings {
         self.commit_message.clone()
     }
 
+    pub fn bump_rel_commit_message(&self) -> String {
+        self.bump_rel_commit_message.clone()
+    }
+
     pub fn push(&self) -> bool {
         self.push
     }
@@ -76,6 +111,58 @@ impl Settings {
         self.srcinfo
     }
 
+    // Base URL of GitHub REST API, points to a mock server for offline testing
+    pub fn github_api(&self) -> String {
+        self.github_api.trim_end_matches('/').to_string()
+    }
+
+    // Whether GitHub releases marked as pre-releases are considered
+    pub fn github_prerelease(&self) -> bool {
+        self.github_prerelease
+    }
+
+    pub fn github_token(&self) -> Option<String> {
+        self.github_token.clone()
+    }
+
+    // Base URL of PyPI JSON API, `/pypi/<project>/json` is appended
+    pub fn pypi_api(&self) -> String {
+        self.pypi_api.trim_end_matches('/').to_string()
+    }
+
+    // Base URL of crates.io API, `/api/v1/crates/<name>` is appended
+    pub fn crates_api(&self) -> String {
+        self.crates_api.trim_end_matches('/').to_string()
+    }
+
+    pub fn npm_registry(&self) -> String {
+        self.npm_registry.trim_end_matches('/').to_string()
+    }
+
+    // Whether the highest npm version is taken instead of `dist-tags.latest`
+    pub fn npm_highest_version(&self) -> bool {
+        self.npm_highest_version
+    }
+
+    // Suite of APT repositories whose Packages index is read, upstreams may set their own
+    pub fn apt_suite(&self) -> String {
+        self.apt_suite.clone()
+    }
+
+    // API base of a GitLab instance, `base` is `https://<host>`
+    pub fn gitlab_api(&self, base: &str) -> String {
+        forge_api(&self.gitlab_api, base, "api/v4")
+    }
+
+    // API base of a Gitea or Forgejo instance, `base` is `https://<host>`
+    pub fn gitea_api(&self, base: &str) -> String {
+        forge_api(&self.gitea_api, base, "api/v1")
+    }
+
+    pub fn package(&self, pkgbase: &str) -> Package {
+        self.packages.get(pkgbase).cloned().unwrap_or_default()
+    }
+
     pub fn build_type(&self) -> Build {
         self.build.clone()
     }
@@ -84,3 +171,12 @@ impl Settings {
         self.chroot.clone()
     }
 }
+
+// Configured API base for a host, or the default location on the host itself
+fn forge_api(configured: &HashMap<String, String>, base: &str, default_path: &str) -> String {
+    let host = base.split("://").last().unwrap();
+    match configured.get(host) {
+        Some(api) => api.trim_end_matches('/').to_string(),
+        None => format!("{}/{}", base, default_path),
+    }
+}
@@ -1,66 +1,205 @@
-use version_compare::{CompOp, VersionCompare};
-
 use std::cell::RefCell;
+use std::path::PathBuf;
 use std::rc::Weak;
 
-use crate::context::Context;
-use crate::pkgbuild::Pkgbuild;
+use regex::Regex;
+
+use crate::pkgbuild::{self, Pkgbuild};
+use crate::settings::Settings;
 use crate::update::Update;
+use crate::version::PkgVersion;
+use upstream::Upstream;
 
 mod remote;
+pub mod upstream;
 
 pub struct Source {
     raw: String,
+    // Without the `name::` prefix which only renames the downloaded file
+    url: String,
     origin: Origin,
     pkgbuild: Weak<RefCell<Pkgbuild>>,
+    // Set for `source_<arch>` entries
+    pub arch: Option<String>,
+    // Position in its source array
     pub index: usize,
+    // Configured instead of guessed from the URL
+    upstream: Option<Upstream>,
 }
 
 impl Source {
     pub fn new(
         raw: String,
-        origin: Origin,
         pkgbuild: Weak<RefCell<Pkgbuild>>,
+        arch: Option<String>,
         index: usize,
     ) -> Source {
+        // `::` after the protocol is part of the URL, like `${_name::1}`
+        let prefix = raw
+            .find("://")
+            .map_or(raw.as_str(), |position| &raw[..position]);
+        let url = match prefix.find("::") {
+            Some(position) => raw[position + 2..].to_string(),
+            None => raw.clone(),
+        };
+        let origin = Origin::guess(url.clone());
         Source {
             raw,
+            url,
             origin,
             pkgbuild,
+            arch,
             index,
+            upstream: None,
         }
     }
+
+    pub fn set_upstream(&mut self, upstream: Upstream) {
+        self.upstream = Some(upstream);
+    }
+
+    #[allow(dead_code)]
     pub fn origin(&self) -> Origin {
         self.origin.clone()
     }
 
+    #[allow(dead_code)]
     pub fn raw(&self) -> String {
         self.raw.clone()
     }
 
-    pub fn update_available(&self) -> Result<Option<Update>, Box<dyn std::error::Error>> {
-        match &self.origin {
-            Origin::Local => Ok(None),
+    // Source without the `name::` prefix
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    // Expands variables of a part of the source, `${pkgver}` becomes the given version
+    pub fn render(&self, template: &str, pkgver: &str) -> String {
+        let pkgbuild = self.pkgbuild.upgrade().unwrap();
+        let pkgbuild = pkgbuild.borrow();
+        let mut rendered = template.to_string();
+        for var in pkgbuild::find_variables(template.to_string()) {
+            let value = if var == "pkgver" {
+                Some(pkgver.to_string())
+            } else {
+                pkgbuild.render(var.clone())
+            };
+            if let Some(value) = value {
+                rendered = rendered.replace(&format!("${{{}}}", var), &value);
+            }
+        }
+        rendered
+    }
+
+    // URL upstream is checked at, with every variable but `${pkgver}` expanded
+    pub fn template(&self) -> String {
+        let url = self
+            .upstream
+            .as_ref()
+            .and_then(|upstream| upstream.url.as_ref())
+            .unwrap_or(&self.url);
+        self.render(url, "${pkgver}")
+    }
+
+    // Regex capturing `version` from names upstream lists, like tags or files.
+    // Configured one wins over the one made from a part of the template.
+    pub fn version_regex(&self, template: &str) -> Result<Regex, Box<dyn std::error::Error>> {
+        if let Some(regex) = self.upstream.as_ref().and_then(|u| u.regex.as_ref()) {
+            return Ok(Regex::new(regex)?);
+        }
+        let pattern = template
+            .split("${pkgver}")
+            .map(|part| regex::escape(&self.render(part, "")))
+            .collect::<Vec<String>>()
+            .join("(?P<version>[^/]+?)");
+        Ok(Regex::new(&format!("^{}$", pattern))?)
+    }
+
+    // Tag template, from configured prefix or a part of the URL holding a tag
+    pub fn tag_template(&self, from_url: &str) -> Option<String> {
+        match self.upstream.as_ref().and_then(|u| u.tag_prefix.as_ref()) {
+            Some(prefix) => Some(format!("{}${{pkgver}}", prefix)),
+            None if from_url.contains("${pkgver}") => Some(from_url.to_string()),
+            None => None,
+        }
+    }
+
+    // CSS selector of elements a scraped page is narrowed down to
+    pub fn selector(&self) -> Option<String> {
+        self.upstream.as_ref().and_then(|u| u.selector.clone())
+    }
+
+    pub fn upstream(&self) -> Option<&Upstream> {
+        self.upstream.as_ref()
+    }
+
+    // Directory of the PKGBUILD, unless it isn't read from a file
+    pub fn directory(&self) -> Option<PathBuf> {
+        let pkgbuild = self.pkgbuild.upgrade().unwrap();
+        let pkgbuild = pkgbuild.borrow();
+        pkgbuild
+            .path()
+            .as_ref()
+            .and_then(|path| path.parent())
+            .map(|directory| directory.to_path_buf())
+    }
+
+    // Remote a source is checked at, None for ones which have nothing to update
+    fn remote(&self) -> Option<remote::Remote> {
+        let origin = match self.upstream.as_ref().and_then(|u| u.remote()) {
+            Some(remote) => Origin::Remote(remote),
+            None => self.origin.clone(),
+        };
+        match origin {
+            // VCS sources follow a branch or a tag on their own and are checked with SKIP
+            Origin::Local | Origin::Vcs => None,
             Origin::Remote(remote) => {
+                // Files which don't change with pkgver, like a LICENSE, have nothing to update
+                if !self.url.contains("${pkgver}") {
+                    return None;
+                }
+                let pkgbuild = self.pkgbuild.upgrade().unwrap();
+                if pkgbuild.borrow().is_skipped(&self.arch, self.index) {
+                    return None;
+                }
+                Some(remote)
+            }
+        }
+    }
+
+    // Whether the source changes with pkgver and is checked for updates
+    pub fn is_checked(&self) -> bool {
+        self.remote().is_some()
+    }
+
+    pub fn update_available(
+        &self,
+        settings: &Settings,
+    ) -> Result<Option<Update>, Box<dyn std::error::Error>> {
+        match self.remote() {
+            None => Ok(None),
+            Some(remote) => {
+                let pkgbuild = self.pkgbuild.upgrade().unwrap();
                 //get latest
-                let latest = remote.latest(self)?;
-                let current;
-                if self.raw.contains("${pkgver}") {
-                    current = self
-                        .pkgbuild
-                        .upgrade()
-                        .unwrap()
-                        .borrow()
-                        .version()
-                        .as_ref()
-                        .unwrap()
-                        .clone();
-                } else {
-                    let error: Box<dyn std::error::Error> =
-                        String::from("Unable to extract current version of a source").into();
-                    return Err(error);
+                let mut latest = remote.latest(self, settings)?;
+                // Upstream found a version somewhere else, the file still comes from the source
+                // unless upstream points to it as well. A checksum only describes that file
+                // when upstream was told where to find one.
+                if let Some(upstream) = self
+                    .upstream
+                    .as_ref()
+                    .filter(|u| u.url.is_some() && u.url_path.is_none())
+                {
+                    latest.url = self.render(&self.url, &latest.version);
+                    if upstream.checksum_path.is_none() {
+                        latest.checksum = None;
+                    }
                 }
-                if VersionCompare::compare_to(&latest.version, &current, &CompOp::Gt).unwrap() {
+                let current = pkgbuild.borrow().pkg_version();
+                // Upstream doesn't know about epoch, and pkgrel isn't compared without one
+                let latest_version =
+                    PkgVersion::new(current.epoch.clone(), latest.version.clone(), None);
+                if latest_version > current {
                     Ok(Some(latest))
                 } else {
                     Ok(None)
@@ -73,13 +212,20 @@ impl Source {
 #[derive(Debug, Clone)]
 pub enum Origin {
     Local,
+    Vcs,
     Remote(remote::Remote),
 }
 
 impl Origin {
     pub fn guess(source: String) -> Origin {
-        // TODO: How is it done in libalpm?
-        if source.contains("http://") || source.contains("https://") {
+        // Same as makepkg: protocol is what's before `://`, VCS ones may have `+transport`
+        if let Some(position) = source.find("://") {
+            let protocol = source[..position].split('+').next().unwrap();
+            if ["bzr", "fossil", "git", "hg", "svn"].contains(&protocol) {
+                return Origin::Vcs;
+            }
+        }
+        if source.starts_with("http://") || source.starts_with("https://") {
             let remote = remote::Remote::guess(source);
             return Origin::Remote(remote);
         }
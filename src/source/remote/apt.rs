@@ -0,0 +1,124 @@
+use flate2::read::GzDecoder;
+use xz2::read::XzDecoder;
+
+use std::cmp::Ordering;
+use std::error::Error;
+use std::io::Read;
+
+use super::deb::DebFile;
+use crate::pkgbuild::HashAlg;
+use crate::settings::Settings;
+use crate::source::Source;
+use crate::update::Update;
+use crate::version::vercmp;
+
+// Entry of a Packages index, only the fields we need
+#[derive(Default, Debug)]
+struct Package {
+    name: String,
+    architecture: String,
+    filename: String,
+    sha256: Option<String>,
+}
+
+// https://<repo>/pool/<component>/<p>/<name>/<name>_${pkgver}-<rev>_<arch>.deb
+pub fn latest(source: &Source, settings: &Settings) -> Result<Option<Update>, Box<dyn Error>> {
+    let url = source.template();
+    let (repo, pool) = match url.split_once("/pool/") {
+        Some(parts) => parts,
+        None => return Err(format!("Unable to find APT repository pool in {}", url).into()),
+    };
+    let component = pool.split('/').next().unwrap();
+    let file_template = url.rsplit('/').next().unwrap();
+    let (name, arch) = match DebFile::parse(file_template) {
+        Some(file) if !file.name.contains('$') && !file.arch.contains('$') => {
+            (file.name, file.arch)
+        }
+        _ => return Err(format!("Unable to find package name and arch in {}", url).into()),
+    };
+
+    let suite = match source
+        .upstream()
+        .and_then(|upstream| upstream.suite.clone())
+    {
+        Some(suite) => suite,
+        None => settings.apt_suite(),
+    };
+    let index = index(repo, &suite, component, &arch)?;
+    let file_regex = source.version_regex(file_template)?;
+    let mut latest: Option<(String, Package)> = None;
+    for package in parse(&index)
+        .into_iter()
+        .filter(|package| package.name == name && package.architecture == arch)
+    {
+        let file = package.filename.rsplit('/').next().unwrap();
+        let version = match file_regex.captures(file) {
+            Some(captures) => captures["version"].to_string(),
+            None => continue,
+        };
+        match latest {
+            Some((ref l, _)) if vercmp(&version, l) != Ordering::Greater => {}
+            _ => latest = Some((version, package)),
+        }
+    }
+
+    Ok(latest.map(|(version, package)| Update {
+        version,
+        source_arch: source.arch.clone(),
+        source_index: source.index,
+        url: format!("{}/{}", repo, package.filename),
+        checksum: package.sha256.map(|sha256| (HashAlg::SHA256, sha256)),
+    }))
+}
+
+// Fetches `dists/<suite>/<component>/binary-<arch>/Packages` trying compressed ones first
+fn index(repo: &str, suite: &str, component: &str, arch: &str) -> Result<String, Box<dyn Error>> {
+    let base = format!(
+        "{}/dists/{}/{}/binary-{}/Packages",
+        repo, suite, component, arch
+    );
+    for extension in &[".xz", ".gz", ""] {
+        let url = format!("{}{}", base, extension);
+        let response = reqwest::blocking::get(&url)?;
+        if !response.status().is_success() {
+            continue;
+        }
+        let content = response.bytes()?;
+        let mut index = String::new();
+        match *extension {
+            ".xz" => XzDecoder::new(&content[..]).read_to_string(&mut index)?,
+            ".gz" => GzDecoder::new(&content[..]).read_to_string(&mut index)?,
+            _ => (&content[..]).read_to_string(&mut index)?,
+        };
+        return Ok(index);
+    }
+    Err(format!("Unable to find Packages index at {}", base).into())
+}
+
+// Stanzas are separated by blank lines, continuation lines start with a space
+fn parse(index: &str) -> Vec<Package> {
+    let mut packages = Vec::new();
+    for stanza in index.split("\n\n") {
+        let mut package = Package::default();
+        for line in stanza.lines() {
+            if line.starts_with(' ') {
+                continue;
+            }
+            let (field, value) = match line.split_once(':') {
+                Some((field, value)) => (field, value.trim().to_string()),
+                None => continue,
+            };
+            match field {
+                "Package" => package.name = value,
+                "Architecture" => package.architecture = value,
+                "Filename" => package.filename = value,
+                "SHA256" => package.sha256 = Some(value),
+                _ => {}
+            }
+        }
+        if !package.name.is_empty() && !package.filename.is_empty() {
+            packages.push(package);
+        }
+    }
+    packages
+}
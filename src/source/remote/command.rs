@@ -0,0 +1,174 @@
+// Escape hatch for upstreams nothing else handles, a shell command prints the version
+
+use serde_json::Value;
+use wait_timeout::ChildExt;
+
+use std::error::Error;
+use std::io::Read;
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use super::checksum_alg;
+use crate::source::Source;
+use crate::update::Update;
+
+// Seconds a command gets unless configured otherwise
+const TIMEOUT: u64 = 60;
+
+// What a command printed, either as `key=value` lines or a JSON object
+#[derive(Default, Debug)]
+struct Output {
+    version: Option<String>,
+    url: Option<String>,
+    checksum: Option<String>,
+    checksum_alg: Option<String>,
+}
+
+// Runs `sh -c <command>` next to the PKGBUILD
+pub fn latest(source: &Source) -> Result<Option<Update>, Box<dyn Error>> {
+    let upstream = source.upstream().unwrap();
+    let command = upstream.command.as_ref().unwrap();
+    let timeout = upstream.timeout.unwrap_or(TIMEOUT);
+    let stdout = run(command, source, Duration::from_secs(timeout))?;
+
+    let output = parse(&stdout)?;
+    let version = match output.version {
+        Some(version) if !version.is_empty() => version,
+        _ => return Ok(None),
+    };
+    let checksum = match output.checksum {
+        Some(checksum) => {
+            let checksum = checksum.to_lowercase();
+            let configured = output
+                .checksum_alg
+                .or_else(|| upstream.checksum_alg.clone());
+            Some((checksum_alg(configured.as_deref(), &checksum)?, checksum))
+        }
+        None => None,
+    };
+    Ok(Some(Update {
+        url: match output.url {
+            Some(url) => url,
+            None => source.render(&source.url(), &version),
+        },
+        version,
+        source_arch: source.arch.clone(),
+        source_index: source.index,
+        checksum,
+    }))
+}
+
+fn run(command: &str, source: &Source, timeout: Duration) -> Result<String, Box<dyn Error>> {
+    let mut sh = Command::new("sh");
+    sh.arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // Own process group, so a timeout kills whatever the command started too
+        .process_group(0);
+    if let Some(directory) = source.directory() {
+        sh.current_dir(directory);
+    }
+    let mut child = match sh.spawn() {
+        Ok(child) => child,
+        Err(why) => return Err(format!("Unable to run `{}`: {}", command, why).into()),
+    };
+
+    // Pipes are drained while waiting, a command filling one up would hang otherwise
+    let mut stdout = child.stdout.take().unwrap();
+    let mut stderr = child.stderr.take().unwrap();
+    let stdout = thread::spawn(move || {
+        let mut output = String::new();
+        stdout.read_to_string(&mut output).map(|_| output)
+    });
+    let stderr = thread::spawn(move || {
+        let mut output = String::new();
+        stderr.read_to_string(&mut output).map(|_| output)
+    });
+
+    let status = match child.wait_timeout(timeout)? {
+        Some(status) => status,
+        None => {
+            let killed = Command::new("kill")
+                .arg("-KILL")
+                .arg("--")
+                .arg(format!("-{}", child.id()))
+                .status();
+            if !matches!(killed, Ok(status) if status.success()) {
+                child.kill()?;
+            }
+            child.wait()?;
+            let stderr = stderr.join().unwrap().unwrap_or_default();
+            return Err(format!(
+                "`{}` didn't finish in {} seconds:\n {}",
+                command,
+                timeout.as_secs(),
+                stderr
+            )
+            .into());
+        }
+    };
+    let stdout = stdout.join().unwrap()?;
+    let stderr = stderr.join().unwrap()?;
+    if !status.success() {
+        return Err(format!("`{}` failed with {}:\n {}", command, status, stderr).into());
+    }
+    Ok(stdout)
+}
+
+// A JSON object, `key=value` lines, or just the version on its own
+fn parse(stdout: &str) -> Result<Output, Box<dyn Error>> {
+    let stdout = stdout.trim();
+    let mut output = Output::default();
+    if stdout.starts_with('{') {
+        let object: Value = match serde_json::from_str(stdout) {
+            Ok(object) => object,
+            Err(why) => return Err(format!("Unable to parse command output: {}", why).into()),
+        };
+        for (key, value) in object.as_object().unwrap() {
+            let value = match value {
+                Value::String(value) => value.clone(),
+                Value::Number(value) => value.to_string(),
+                Value::Null => continue,
+                _ => return Err(format!("Command printed {} as {}", key, value).into()),
+            };
+            output.set(key, value)?;
+        }
+        return Ok(output);
+    }
+
+    let lines: Vec<&str> = stdout
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    if let [version] = lines[..] {
+        if !version.contains('=') {
+            output.version = Some(version.to_string());
+            return Ok(output);
+        }
+    }
+    for line in lines {
+        match line.split_once('=') {
+            Some((key, value)) => output.set(key.trim(), value.trim().to_string())?,
+            None => return Err(format!("Unable to parse command output line {}", line).into()),
+        }
+    }
+    Ok(output)
+}
+
+impl Output {
+    fn set(&mut self, key: &str, value: String) -> Result<(), Box<dyn Error>> {
+        match key {
+            "version" => self.version = Some(value),
+            "url" => self.url = Some(value),
+            "checksum" => self.checksum = Some(value),
+            "checksum_alg" => self.checksum_alg = Some(value),
+            _ => return Err(format!("Command printed unknown key {}", key).into()),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+use serde::Deserialize;
+
+use std::cmp::Ordering;
+use std::error::Error;
+
+use super::get_json;
+use crate::pkgbuild::HashAlg;
+use crate::settings::Settings;
+use crate::source::Source;
+use crate::update::Update;
+use crate::version::vercmp;
+
+#[derive(Deserialize, Debug)]
+struct Crate {
+    versions: Vec<Version>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Version {
+    num: String,
+    yanked: bool,
+    // sha256 of the .crate file
+    checksum: String,
+}
+
+// https://static.crates.io/crates/<name>/<name>-${pkgver}.crate
+// https://crates.io/api/v1/crates/<name>/${pkgver}/download
+pub fn latest(source: &Source, settings: &Settings) -> Result<Option<Update>, Box<dyn Error>> {
+    let url = source.template();
+    let name = url
+        .split("/crates/")
+        .nth(1)
+        .and_then(|path| path.split('/').next())
+        .filter(|name| !name.is_empty() && !name.contains('$'));
+    let name = match name {
+        Some(name) => name,
+        None => return Err(format!("Unable to find crate name in {}", url).into()),
+    };
+
+    let versions =
+        get_json::<Crate>(&format!("{}/api/v1/crates/{}", settings.crates_api(), name))?.versions;
+    let mut latest: Option<&Version> = None;
+    // Pre-releases have `-` which pkgver can't hold anyway
+    for version in versions
+        .iter()
+        .filter(|version| !version.yanked && !version.num.contains('-'))
+    {
+        match latest {
+            Some(l) if vercmp(&version.num, &l.num) != Ordering::Greater => {}
+            _ => latest = Some(version),
+        }
+    }
+
+    Ok(latest.map(|version| Update {
+        version: version.num.clone(),
+        source_arch: source.arch.clone(),
+        source_index: source.index,
+        url: source.render(&url, &version.num),
+        checksum: Some((HashAlg::SHA256, version.checksum.clone())),
+    }))
+}
@@ -1,39 +1,73 @@
-use select::document::Document;
-use select::predicate::Name;
-use version_compare::{CompOp, VersionCompare};
-
+use std::cmp::Ordering;
 use std::error::Error;
 
+use super::listing;
 use crate::source::Source;
 use crate::update::Update;
+use crate::version::vercmp;
 
 pub fn latest(source: &Source) -> Result<Option<Update>, Box<dyn Error>> {
-    let raw = source.raw();
-    let repo_url = parent(raw);
-    let packages = list(repo_url.clone())?;
-    let versions: Vec<Update> = packages
-        .iter()
-        // filter out unrelated packages
-        .map(|x| Update {
-            version: extract_version_by_template(x.to_string(), source),
-            source_index: source.index,
-            url: format!("{}/{}", repo_url, x.to_string()),
-        })
+    let template = source.template();
+    let wanted = match DebFile::parse(&filename_part(template.clone())) {
+        Some(wanted) if !wanted.name.contains('$') && !wanted.arch.contains('$') => wanted,
+        _ => return Err(format!("Unable to parse {} as a Debian package name", template).into()),
+    };
+    let version_regex = source.version_regex(&wanted.version)?;
+    let repo_url = parent(template);
+    let packages: Vec<String> = listing::links(&repo_url)?
+        .into_iter()
+        .filter(|link| link.ends_with(".deb"))
         .collect();
     let mut latest_version: Option<Update> = None;
-    for version in versions {
+    for package in &packages {
+        // Other packages and architectures often share a pool directory
+        let file = match DebFile::parse(&filename_part(package.to_string())) {
+            Some(file) if file.name == wanted.name && file.arch == wanted.arch => file,
+            _ => continue,
+        };
+        let version = match version_regex.captures(&file.version) {
+            Some(captures) => captures["version"].to_string(),
+            None => continue,
+        };
+        let update = Update {
+            version,
+            source_arch: source.arch.clone(),
+            source_index: source.index,
+            url: format!("{}/{}", repo_url, filename_part(package.to_string())),
+            checksum: None,
+        };
         match latest_version {
-            None => latest_version = Some(version),
-            Some(ref l) => {
-                if VersionCompare::compare_to(&version.version, &l.version, &CompOp::Ge).unwrap() {
-                    latest_version = Some(version);
-                }
-            }
+            Some(ref l) if vercmp(&update.version, &l.version) == Ordering::Less => {}
+            _ => latest_version = Some(update),
         }
     }
     Ok(latest_version)
 }
 
+// <name>_<version>_<arch>.deb, as Debian names package files
+pub struct DebFile {
+    pub name: String,
+    pub version: String,
+    pub arch: String,
+}
+
+impl DebFile {
+    pub fn parse(filename: &str) -> Option<DebFile> {
+        let stem = filename.strip_suffix(".deb")?;
+        let mut parts = stem.split('_');
+        let file = DebFile {
+            name: parts.next()?.to_string(),
+            // Epoch colon is escaped in URLs
+            version: parts.next()?.replace("%3a", ":").replace("%3A", ":"),
+            arch: parts.next()?.to_string(),
+        };
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(file)
+    }
+}
+
 // Extracts URL to a parent directory
 fn parent(source: String) -> String {
     let position = source.rfind('/').unwrap();
@@ -42,55 +76,5 @@ fn parent(source: String) -> String {
 }
 
 fn filename_part(source: String) -> String {
-    let position = source.rfind('/').unwrap();
-    let (_, file) = source.split_at(position + 1); // I don't like +1
-    file.to_string()
-}
-
-// Lists all packages available in the repository
-// TODO: filter out other packages to have all available versions of a single one
-fn list(url: String) -> Result<Vec<String>, Box<dyn Error>> {
-    let resp = reqwest::blocking::get(&url)?;
-    let mut packages = Vec::new();
-    Document::from_read(resp)
-        .unwrap()
-        .find(Name("a"))
-        .filter_map(|n| n.attr("href"))
-        .for_each(|x| {
-            if x.contains(".deb") {
-                packages.push(x.to_string())
-            }
-        });
-    Ok(packages)
-}
-
-// Takes version prefix and postfix from template and removes them from repo filename
-// We hope it leaves us with a version of a remote file
-fn extract_version_by_template(file: String, source: &Source) -> String {
-    //${_pkgname}_${pkgver}-1_amd64.deb
-    //microsoft-edge-dev_88.0.680.1-1_amd64.deb
-    let mut version = file;
-    let template = filename_part(source.raw());
-    let surroundings = template.split("${pkgver}");
-    for this in surroundings {
-        let mut render = this.to_string();
-        let vars = crate::pkgbuild::find_variables(this.to_string());
-        for var in vars {
-            if render.contains(&var) {
-                // TODO: look for all variables
-                render = render.replace(
-                    &format!("${{{}}}", var),
-                    &source
-                        .pkgbuild
-                        .upgrade()
-                        .unwrap()
-                        .borrow()
-                        .render(var.clone())
-                        .unwrap(),
-                );
-            }
-        }
-        version = version.replace(&render, "");
-    }
-    version
+    source.rsplit('/').next().unwrap().to_string()
 }
@@ -0,0 +1,41 @@
+use serde::Deserialize;
+
+use std::error::Error;
+
+use super::{archive_tag, get_json, highest_tag, split_host};
+use crate::settings::Settings;
+use crate::source::Source;
+use crate::update::Update;
+
+#[derive(Deserialize, Debug)]
+struct Tag {
+    name: String,
+}
+
+// https://<host>/<owner>/<repo>/archive/<tag>.tar.gz
+// https://<host>/<owner>/<repo>, with a configured tag prefix
+pub fn latest_tag(source: &Source, settings: &Settings) -> Result<Option<Update>, Box<dyn Error>> {
+    let url = source.template();
+    let (base, path) = match split_host(&url) {
+        Some(parts) => parts,
+        None => return Err(format!("Unable to find Gitea host in {}", url).into()),
+    };
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let tag = match segments.as_slice() {
+        [_, _, "archive", file] => archive_tag(file).unwrap_or_default(),
+        [_, _] => "",
+        _ => return Err(format!("Unable to find Gitea repository in {}", url).into()),
+    };
+    let tag = match source.tag_template(tag) {
+        Some(tag) => tag,
+        None => return Err(format!("Unable to map Gitea tags of {} to pkgver", url).into()),
+    };
+
+    let tags: Vec<Tag> = get_json(&format!(
+        "{}/repos/{}/{}/tags?limit=50",
+        settings.gitea_api(base),
+        segments[0],
+        segments[1]
+    ))?;
+    highest_tag(source, &tag, tags.into_iter().map(|tag| tag.name).collect())
+}
@@ -0,0 +1,355 @@
+use reqwest::blocking::Response;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use std::error::Error;
+use std::process::Command;
+use std::str;
+
+use super::{archive_tag, highest_tag};
+use crate::settings::Settings;
+use crate::source::Source;
+use crate::update::Update;
+
+#[derive(Deserialize, Debug)]
+struct Release {
+    tag_name: String,
+    draft: bool,
+    prerelease: bool,
+}
+
+#[derive(Deserialize, Debug)]
+struct Tag {
+    name: String,
+}
+
+// Repository a github.com URL points to and the part of it holding a tag
+struct Repo {
+    owner: String,
+    name: String,
+    // Tag template, `v${pkgver}` and alike
+    tag: String,
+}
+
+impl Repo {
+    // https://github.com/<owner>/<repo>/releases/download/<tag>/<file>
+    // https://github.com/<owner>/<repo>/archive/[refs/tags/]<tag>.tar.gz
+    // https://github.com/<owner>/<repo>, with a configured tag prefix
+    fn parse(url: &str) -> Option<Repo> {
+        let path = url.split("github.com/").nth(1)?;
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        if segments.len() < 2 {
+            return None;
+        }
+        let tag = match segments.get(2) {
+            Some(&"releases") if segments.get(3) == Some(&"download") => {
+                segments.get(4)?.to_string()
+            }
+            Some(&"archive") => archive_tag(segments.last()?)?.to_string(),
+            _ => String::new(),
+        };
+        Some(Repo {
+            owner: segments[0].to_string(),
+            name: segments[1].trim_end_matches(".git").to_string(),
+            tag,
+        })
+    }
+}
+
+pub fn latest_release(
+    source: &Source,
+    settings: &Settings,
+) -> Result<Option<Update>, Box<dyn Error>> {
+    let repo = repo(source)?;
+    let releases: Vec<Release> = api(
+        &format!("repos/{}/{}/releases?per_page=100", repo.owner, repo.name),
+        settings,
+    )?;
+    highest_tag(
+        source,
+        &repo.tag,
+        published(releases, settings.github_prerelease()),
+    )
+}
+
+// Tags of releases which are out, drafts aren't and pre-releases only if asked for
+fn published(releases: Vec<Release>, prerelease: bool) -> Vec<String> {
+    releases
+        .into_iter()
+        .filter(|release| !release.draft)
+        .filter(|release| !release.prerelease || prerelease)
+        .map(|release| release.tag_name)
+        .collect()
+}
+
+// For projects which only push tags, `/archive/` URLs are made from those
+pub fn latest_tag(source: &Source, settings: &Settings) -> Result<Option<Update>, Box<dyn Error>> {
+    let repo = repo(source)?;
+    // Tags aren't listed in version order, the newest may be on any page
+    let tags = match api_pages::<Tag>(
+        &format!("repos/{}/{}/tags?per_page=100", repo.owner, repo.name),
+        settings,
+    ) {
+        Ok(tags) => tags.into_iter().map(|tag| tag.name).collect(),
+        // API is rate limited for anonymous users, git isn't
+        Err(why) => {
+            println!("{}, falling back to git ls-remote", why);
+            ls_remote(&repo)?
+        }
+    };
+    highest_tag(source, &repo.tag, tags)
+}
+
+fn repo(source: &Source) -> Result<Repo, Box<dyn Error>> {
+    let url = source.template();
+    let mut repo = match Repo::parse(&url) {
+        Some(repo) => repo,
+        None => return Err(format!("Unable to find GitHub repository in {}", url).into()),
+    };
+    repo.tag = match source.tag_template(&repo.tag) {
+        Some(tag) => tag,
+        None => return Err(format!("Unable to map GitHub tags of {} to pkgver", url).into()),
+    };
+    Ok(repo)
+}
+
+fn api<T: DeserializeOwned>(path: &str, settings: &Settings) -> Result<T, Box<dyn Error>> {
+    Ok(get(&format!("{}/{}", settings.github_api(), path), settings)?.json()?)
+}
+
+// Every page of a list, each one links to the next in its Link header
+fn api_pages<T: DeserializeOwned>(
+    path: &str,
+    settings: &Settings,
+) -> Result<Vec<T>, Box<dyn Error>> {
+    let mut items = Vec::new();
+    let mut url = Some(format!("{}/{}", settings.github_api(), path));
+    while let Some(page) = url {
+        let response = get(&page, settings)?;
+        url = response
+            .headers()
+            .get("Link")
+            .and_then(|link| link.to_str().ok())
+            .and_then(next_page);
+        items.extend(response.json::<Vec<T>>()?);
+    }
+    Ok(items)
+}
+
+// `<https://api.github.com/...&page=2>; rel="next", <...>; rel="last"`
+fn next_page(link: &str) -> Option<String> {
+    link.split(',').find_map(|link| {
+        let (url, rel) = link.split_once(';')?;
+        if rel.trim() != "rel=\"next\"" {
+            return None;
+        }
+        Some(url.trim().strip_prefix('<')?.strip_suffix('>')?.to_string())
+    })
+}
+
+fn get(url: &str, settings: &Settings) -> Result<Response, Box<dyn Error>> {
+    let client = reqwest::blocking::Client::new();
+    // GitHub rejects requests without User-Agent
+    let mut request = client
+        .get(url)
+        .header("User-Agent", "pacops")
+        .header("Accept", "application/vnd.github.v3+json");
+    if let Some(token) = settings.github_token() {
+        request = request.header("Authorization", format!("token {}", token));
+    }
+    let response = request.send()?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "GitHub API responded with {} for {}",
+            response.status(),
+            url
+        )
+        .into());
+    }
+    Ok(response)
+}
+
+fn ls_remote(repo: &Repo) -> Result<Vec<String>, Box<dyn Error>> {
+    let git = Command::new("git")
+        .arg("ls-remote")
+        .arg("--tags")
+        .arg(format!(
+            "https://github.com/{}/{}.git",
+            repo.owner, repo.name
+        ))
+        .output()?;
+    if !git.status.success() {
+        return Err(format!(
+            "Unable to list tags of {}/{}:\n {}",
+            repo.owner,
+            repo.name,
+            str::from_utf8(&git.stderr).unwrap()
+        )
+        .into());
+    }
+    // <sha>\trefs/tags/<tag>, annotated tags are listed twice, second time with ^{}
+    Ok(str::from_utf8(&git.stdout)?
+        .lines()
+        .filter_map(|line| line.split("refs/tags/").nth(1))
+        .filter(|tag| !tag.ends_with("^{}"))
+        .map(|tag| tag.to_string())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pkgbuild::Pkgbuild;
+
+    use std::cell::RefCell;
+    use std::fs;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::rc::Rc;
+    use std::thread;
+
+    fn pkgbuild(source: &str) -> Rc<RefCell<Pkgbuild>> {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("PKGBUILD");
+        fs::write(
+            &path,
+            format!(
+                "pkgname=foo\npkgver=1.0.0\npkgrel=1\nsource=(\"{}\")\nsha256sums=(aa)\n",
+                source
+            ),
+        )
+        .unwrap();
+        Pkgbuild::from_file(path.to_str().unwrap()).unwrap()
+    }
+
+    // Settings pointing the API at a local server
+    fn settings(github_api: &str) -> Settings {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("pacops.toml");
+        fs::write(&path, format!("github_api = \"{}\"\n", github_api)).unwrap();
+        Settings::builder(Some(path.to_str().unwrap().to_string()))
+            .unwrap()
+            .try_into()
+            .unwrap()
+    }
+
+    // Answers requests in order with extra headers and JSON bodies, `{url}` in headers
+    // is the server URL which is returned
+    fn serve(responses: Vec<(&'static str, &'static str)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = url.clone();
+        thread::spawn(move || {
+            for (headers, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                // Request head ends with an empty line, GET requests have no body
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n{}",
+                    body.len(),
+                    headers.replace("{url}", &server),
+                    body
+                )
+                .unwrap();
+            }
+        });
+        url
+    }
+
+    fn release(tag_name: &str, draft: bool, prerelease: bool) -> Release {
+        Release {
+            tag_name: tag_name.to_string(),
+            draft,
+            prerelease,
+        }
+    }
+
+    #[test]
+    fn repos_parse() {
+        let repo = Repo::parse("https://github.com/o/foo/releases/download/v${pkgver}/foo.tar.gz")
+            .unwrap();
+        assert_eq!((repo.owner.as_str(), repo.name.as_str()), ("o", "foo"));
+        assert_eq!(repo.tag, "v${pkgver}");
+        let repo =
+            Repo::parse("https://github.com/o/foo/archive/refs/tags/release-${pkgver}.tar.gz")
+                .unwrap();
+        assert_eq!(repo.tag, "release-${pkgver}");
+        let repo = Repo::parse("https://github.com/o/foo.git").unwrap();
+        assert_eq!((repo.name.as_str(), repo.tag.as_str()), ("foo", ""));
+        assert!(Repo::parse("https://github.com/o").is_none());
+        assert!(Repo::parse("https://e.org/o/foo").is_none());
+    }
+
+    #[test]
+    fn drafts_and_prereleases_arent_published() {
+        let releases = || {
+            vec![
+                release("v3.0.0", true, false),
+                release("v2.1.0-rc1", false, true),
+                release("v2.0.0", false, false),
+            ]
+        };
+        assert_eq!(published(releases(), false), ["v2.0.0"]);
+        assert_eq!(published(releases(), true), ["v2.1.0-rc1", "v2.0.0"]);
+    }
+
+    #[test]
+    fn tags_map_to_pkgver() {
+        let pkgbuild =
+            pkgbuild("https://github.com/o/foo/releases/download/v${pkgver}/foo-${pkgver}.tar.gz");
+        let pkgbuild = pkgbuild.borrow();
+        let source = &pkgbuild.sources()[0];
+        let tags = ["v1.2.0", "nightly", "v1.10.0", "foo-9.0", "v1.9.0"]
+            .iter()
+            .map(|tag| tag.to_string())
+            .collect();
+        let update = highest_tag(source, "v${pkgver}", tags).unwrap().unwrap();
+        assert_eq!(update.version, "1.10.0");
+        assert_eq!(
+            update.url,
+            "https://github.com/o/foo/releases/download/v1.10.0/foo-1.10.0.tar.gz"
+        );
+    }
+
+    #[test]
+    fn latest_release_from_api() {
+        let api = serve(vec![(
+            "",
+            r#"[{"tag_name": "v3.0.0", "draft": true, "prerelease": false},
+                {"tag_name": "v2.1.0-rc1", "draft": false, "prerelease": true},
+                {"tag_name": "v2.0.0", "draft": false, "prerelease": false},
+                {"tag_name": "v1.0.0", "draft": false, "prerelease": false}]"#,
+        )]);
+        let pkgbuild = pkgbuild("https://github.com/o/foo/releases/download/v${pkgver}/foo.tar.gz");
+        let pkgbuild = pkgbuild.borrow();
+        let update = latest_release(&pkgbuild.sources()[0], &settings(&api))
+            .unwrap()
+            .unwrap();
+        assert_eq!(update.version, "2.0.0");
+    }
+
+    #[test]
+    fn tags_are_read_from_every_page() {
+        let api = serve(vec![
+            (
+                "Link: <{url}/repositories/1/tags?per_page=100&page=2>; rel=\"next\", <{url}/repositories/1/tags?per_page=100&page=2>; rel=\"last\"\r\n",
+                r#"[{"name": "v1.9.0"}, {"name": "v1.10.0"}]"#,
+            ),
+            (
+                "Link: <{url}/repositories/1/tags?per_page=100&page=1>; rel=\"prev\"\r\n",
+                r#"[{"name": "v2.0.0"}, {"name": "v0.1.0"}]"#,
+            ),
+        ]);
+        let pkgbuild = pkgbuild("https://github.com/o/foo/archive/v${pkgver}.tar.gz");
+        let pkgbuild = pkgbuild.borrow();
+        let update = latest_tag(&pkgbuild.sources()[0], &settings(&api))
+            .unwrap()
+            .unwrap();
+        assert_eq!(update.version, "2.0.0");
+    }
+}
@@ -0,0 +1,39 @@
+use serde::Deserialize;
+
+use std::error::Error;
+
+use super::{get_json, highest_tag, split_host};
+use crate::settings::Settings;
+use crate::source::Source;
+use crate::update::Update;
+
+#[derive(Deserialize, Debug)]
+struct Tag {
+    name: String,
+}
+
+// https://<host>/<group>[/<subgroup>]/<project>/-/archive/<tag>/<file>
+// https://<host>/<group>[/<subgroup>]/<project>, with a configured tag prefix
+pub fn latest_tag(source: &Source, settings: &Settings) -> Result<Option<Update>, Box<dyn Error>> {
+    let url = source.template();
+    let (base, path) = match split_host(&url) {
+        Some(parts) => parts,
+        None => return Err(format!("Unable to find GitLab host in {}", url).into()),
+    };
+    let (project, tag) = match path.split_once("/-/archive/") {
+        Some((project, rest)) => (project, rest.split('/').next().unwrap()),
+        None => (path.trim_end_matches('/').trim_end_matches(".git"), ""),
+    };
+    let tag = match source.tag_template(tag) {
+        Some(tag) => tag,
+        None => return Err(format!("Unable to map GitLab tags of {} to pkgver", url).into()),
+    };
+
+    // Project path is used as an ID, with slashes encoded
+    let tags: Vec<Tag> = get_json(&format!(
+        "{}/projects/{}/repository/tags?per_page=100",
+        settings.gitlab_api(base),
+        project.replace('/', "%2F")
+    ))?;
+    highest_tag(source, &tag, tags.into_iter().map(|tag| tag.name).collect())
+}
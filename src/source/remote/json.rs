@@ -0,0 +1,108 @@
+// "Latest version" endpoints, like update feeds of Electron apps
+
+use regex::Regex;
+use serde_json::Value;
+use serde_json_path::JsonPath;
+
+use std::cmp::Ordering;
+use std::error::Error;
+
+use super::{checksum_alg, get_json};
+use crate::source::Source;
+use crate::update::Update;
+use crate::version::vercmp;
+
+// Paths may match a list of releases, URLs and checksums are then paired by position
+pub fn latest(source: &Source) -> Result<Option<Update>, Box<dyn Error>> {
+    let upstream = source.upstream().unwrap();
+    let url = source.template();
+    let response = get_json::<Value>(&url)?;
+
+    let versions = query(&response, upstream.json_path.as_ref().unwrap())?;
+    // Versions like `v1.0` or `release-1.0` are cut down by a regex
+    let regex = match &upstream.regex {
+        Some(regex) => Some(Regex::new(regex)?),
+        None => None,
+    };
+    let mut latest: Option<(usize, String)> = None;
+    for (position, version) in versions.iter().enumerate() {
+        let version = match &regex {
+            Some(regex) => match regex.captures(version) {
+                Some(captures) => captures["version"].to_string(),
+                None => continue,
+            },
+            None => version.clone(),
+        };
+        match latest {
+            Some((_, ref l)) if vercmp(&version, l) != Ordering::Greater => {}
+            _ => latest = Some((position, version)),
+        }
+    }
+    let (position, version) = match latest {
+        Some(latest) => latest,
+        None => return Ok(None),
+    };
+
+    let download = match &upstream.url_path {
+        Some(path) => paired(&response, path, position, versions.len())?,
+        None => source.render(&source.url(), &version),
+    };
+    let checksum = match &upstream.checksum_path {
+        Some(path) => {
+            let checksum = paired(&response, path, position, versions.len())?.to_lowercase();
+            Some((
+                checksum_alg(upstream.checksum_alg.as_deref(), &checksum)?,
+                checksum,
+            ))
+        }
+        None => None,
+    };
+    Ok(Some(Update {
+        version,
+        source_arch: source.arch.clone(),
+        source_index: source.index,
+        url: download,
+        checksum,
+    }))
+}
+
+// Strings and numbers a path points to
+fn query(response: &Value, path: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let json_path = match JsonPath::parse(path) {
+        Ok(json_path) => json_path,
+        Err(why) => return Err(format!("Unable to parse JSONPath {}: {}", path, why).into()),
+    };
+    json_path
+        .query(response)
+        .all()
+        .into_iter()
+        .map(|value| match value {
+            Value::String(value) => Ok(value.clone()),
+            Value::Number(value) => Ok(value.to_string()),
+            _ => Err(format!("{} points to {}, not a string", path, value).into()),
+        })
+        .collect()
+}
+
+// Value at the position of the version, or the only one there is
+fn paired(
+    response: &Value,
+    path: &str,
+    position: usize,
+    versions: usize,
+) -> Result<String, Box<dyn Error>> {
+    let mut values = query(response, path)?;
+    if values.len() == 1 {
+        return Ok(values.remove(0));
+    }
+    if values.len() == versions {
+        return Ok(values.remove(position));
+    }
+    Err(format!(
+        "{} matches {} values for {} versions, unable to pair them",
+        path,
+        values.len(),
+        versions
+    )
+    .into())
+}
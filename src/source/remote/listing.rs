@@ -0,0 +1,106 @@
+// Apache and nginx autoindex pages, or anything else linking to files
+
+use regex::Regex;
+use select::document::Document;
+use select::predicate::Name;
+
+use std::cmp::Ordering;
+use std::error::Error;
+
+use crate::source::Source;
+use crate::update::Update;
+use crate::version::vercmp;
+
+// How many versioned subdirectories are looked into at every level
+const SUBDIRECTORIES: usize = 3;
+
+// https://example.org/releases/foo-${pkgver}.tar.xz
+// https://example.org/releases/${pkgver%.*}/foo-${pkgver}.tar.xz
+pub fn latest(source: &Source) -> Result<Option<Update>, Box<dyn Error>> {
+    let url = source.template();
+    let (directory, file_template) = url.rsplit_once('/').unwrap();
+    if !file_template.contains("${pkgver}") {
+        return Err(format!("Unable to find pkgver in file name of {}", url).into());
+    }
+    let file_regex = source.version_regex(file_template)?;
+
+    // Newer subdirectories first, the first one with a matching file wins
+    for directory in directories(directory)? {
+        let mut latest: Option<(String, String)> = None;
+        for file in links(&directory)? {
+            let version = match file_regex.captures(&file) {
+                Some(captures) => captures["version"].to_string(),
+                None => continue,
+            };
+            match latest {
+                Some((ref l, _)) if vercmp(&version, l) != Ordering::Greater => {}
+                _ => latest = Some((version, file)),
+            }
+        }
+        if let Some((version, file)) = latest {
+            return Ok(Some(Update {
+                version,
+                source_arch: source.arch.clone(),
+                source_index: source.index,
+                url: format!("{}/{}", directory, file),
+                checksum: None,
+            }));
+        }
+    }
+    Ok(None)
+}
+
+// Directories a template may point to, variables in path are versioned subdirectories
+fn directories(template: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let position = match template.find("${") {
+        Some(position) => position,
+        None => return Ok(vec![template.to_string()]),
+    };
+    let start = template[..position].rfind('/').unwrap();
+    let end = template[position..]
+        .find('/')
+        .map_or(template.len(), |end| position + end);
+    let (parent, segment, rest) = (
+        &template[..start],
+        &template[start + 1..end],
+        &template[end..],
+    );
+
+    // Every variable stands for something, `${pkgver%.*}` included
+    let variable = Regex::new(r"\$\{[^}]*\}").unwrap();
+    let pattern = variable
+        .split(segment)
+        .map(regex::escape)
+        .collect::<Vec<String>>()
+        .join("[^/]+");
+    let segment_regex = Regex::new(&format!("^{}$", pattern))?;
+    let mut subdirectories: Vec<String> = links(parent)?
+        .into_iter()
+        .filter(|link| segment_regex.is_match(link))
+        .collect();
+    subdirectories.sort_by(|a, b| vercmp(b, a));
+    subdirectories.dedup();
+
+    let mut directories = Vec::new();
+    for subdirectory in subdirectories.iter().take(SUBDIRECTORIES) {
+        directories.append(&mut self::directories(&format!(
+            "{}/{}{}",
+            parent, subdirectory, rest
+        ))?);
+    }
+    Ok(directories)
+}
+
+// Names of files and directories a page links to, sorting links and alike aside
+pub fn links(url: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let resp = reqwest::blocking::get(url)?;
+    let links = Document::from_read(resp)?
+        .find(Name("a"))
+        .filter_map(|n| n.attr("href"))
+        .filter(|href| !href.contains('?') && !href.starts_with('#'))
+        .filter_map(|href| href.trim_end_matches('/').rsplit('/').next())
+        .filter(|name| !name.is_empty() && *name != "..")
+        .map(|name| name.to_string())
+        .collect();
+    Ok(links)
+}
@@ -1,32 +1,93 @@
+use serde::Deserialize;
+
+use std::cmp::Ordering;
 use std::error::Error;
 
 use super::Source;
+use crate::pkgbuild::HashAlg;
+use crate::settings::Settings;
 use crate::update::Update;
+use crate::version::vercmp;
 
+mod apt;
+mod command;
+mod crates;
 mod deb;
+mod gitea;
+mod github;
+mod gitlab;
+mod json;
+mod listing;
+mod npm;
+mod pypi;
+mod rpm;
+mod scrape;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum Remote {
     Deb,
     GithubRelease,
+    GithubTag,
+    Gitlab,
+    Gitea,
+    Pypi,
+    Crates,
+    Npm,
+    Rpm,
+    Listing,
+    // Only configured, any page can be scraped, any JSON API read or any command run
+    Regex,
+    Json,
+    Command,
     Unknown,
 }
 
 impl Remote {
     pub fn guess(source: String) -> Remote {
         if source.starts_with("http://") || source.starts_with("https://") {
-            if source.contains("github.com") && source.contains("/archive/") {
+            if source.contains("static.crates.io/crates/")
+                || source.contains("crates.io/api/v1/crates/")
+            {
+                return Remote::Crates;
+            } else if source.contains("registry.npmjs.org/") && source.contains("/-/") {
+                return Remote::Npm;
+            } else if source.contains("files.pythonhosted.org/")
+                || source.contains("pypi.io/packages/")
+            {
+                return Remote::Pypi;
+            } else if source.contains("github.com") && source.contains("/releases/download/") {
                 return Remote::GithubRelease;
+            } else if source.contains("github.com") && source.contains("/archive/") {
+                return Remote::GithubTag;
+            } else if source.contains("/-/archive/") {
+                return Remote::Gitlab;
+            } else if is_forge_archive(&source) {
+                // Gitea, Forgejo and Codeberg which runs it
+                return Remote::Gitea;
             } else if source.ends_with(".deb") {
                 return Remote::Deb;
+            } else if source.ends_with(".rpm") {
+                return Remote::Rpm;
+            } else if source.rsplit('/').next().unwrap().contains("${pkgver}") {
+                // Anything else with a version in its name may be on an autoindex page
+                return Remote::Listing;
             }
         }
         Remote::Unknown
     }
 
-    pub fn latest(&self, source: &Source) -> Result<Update, Box<dyn Error>> {
+    pub fn latest(&self, source: &Source, settings: &Settings) -> Result<Update, Box<dyn Error>> {
         match self {
             Self::Deb => {
+                // Pool files are described by repository indices, with checksums
+                if source.url().contains("/pool/") {
+                    match apt::latest(source, settings) {
+                        Ok(Some(latest)) => return Ok(latest),
+                        Ok(None) => {}
+                        Err(why) => println!("{}, falling back to directory listing", why),
+                    }
+                }
                 if let Some(latest) = deb::latest(source)? {
                     Ok(latest)
                 } else {
@@ -36,9 +97,112 @@ impl Remote {
                 }
             }
             Self::GithubRelease => {
-                let error: Box<dyn Error> =
-                    String::from("Github Release parsing is not implemented yet").into();
-                Err(error)
+                if let Some(latest) = github::latest_release(source, settings)? {
+                    Ok(latest)
+                } else {
+                    let error: Box<dyn Error> =
+                        String::from("Unable to find any matching GitHub releases").into();
+                    Err(error)
+                }
+            }
+            Self::GithubTag => {
+                if let Some(latest) = github::latest_tag(source, settings)? {
+                    Ok(latest)
+                } else {
+                    let error: Box<dyn Error> =
+                        String::from("Unable to find any matching GitHub tags").into();
+                    Err(error)
+                }
+            }
+            Self::Gitlab => {
+                if let Some(latest) = gitlab::latest_tag(source, settings)? {
+                    Ok(latest)
+                } else {
+                    let error: Box<dyn Error> =
+                        String::from("Unable to find any matching GitLab tags").into();
+                    Err(error)
+                }
+            }
+            Self::Gitea => {
+                if let Some(latest) = gitea::latest_tag(source, settings)? {
+                    Ok(latest)
+                } else {
+                    let error: Box<dyn Error> =
+                        String::from("Unable to find any matching Gitea tags").into();
+                    Err(error)
+                }
+            }
+            Self::Pypi => {
+                if let Some(latest) = pypi::latest(source, settings)? {
+                    Ok(latest)
+                } else {
+                    let error: Box<dyn Error> =
+                        String::from("Unable to find any stable PyPI releases").into();
+                    Err(error)
+                }
+            }
+            Self::Crates => {
+                if let Some(latest) = crates::latest(source, settings)? {
+                    Ok(latest)
+                } else {
+                    let error: Box<dyn Error> =
+                        String::from("Unable to find any non-yanked crate versions").into();
+                    Err(error)
+                }
+            }
+            Self::Npm => {
+                if let Some(latest) = npm::latest(source, settings)? {
+                    Ok(latest)
+                } else {
+                    let error: Box<dyn Error> =
+                        String::from("Unable to find the latest npm version").into();
+                    Err(error)
+                }
+            }
+            Self::Rpm => {
+                if let Some(latest) = rpm::latest(source)? {
+                    Ok(latest)
+                } else {
+                    let error: Box<dyn Error> =
+                        String::from("Unable to find any matching RPM packages").into();
+                    Err(error)
+                }
+            }
+            Self::Listing => {
+                if let Some(latest) = listing::latest(source)? {
+                    Ok(latest)
+                } else {
+                    let error: Box<dyn Error> =
+                        String::from("Unable to find any matching files in directory index").into();
+                    Err(error)
+                }
+            }
+            Self::Regex => {
+                if let Some(latest) = scrape::latest(source)? {
+                    Ok(latest)
+                } else {
+                    let error: Box<dyn Error> =
+                        String::from("Unable to find any versions matching the regex").into();
+                    Err(error)
+                }
+            }
+            Self::Json => {
+                if let Some(latest) = json::latest(source)? {
+                    Ok(latest)
+                } else {
+                    let error: Box<dyn Error> =
+                        String::from("Unable to find a version in the JSON response").into();
+                    Err(error)
+                }
+            }
+            Self::Command => {
+                if let Some(latest) = command::latest(source)? {
+                    Ok(latest)
+                } else {
+                    let error: Box<dyn Error> =
+                        String::from("Upstream command didn't print a version").into();
+                    Err(error)
+                }
             }
             Self::Unknown => {
                 let error: Box<dyn Error> =
@@ -48,3 +212,116 @@ impl Remote {
         }
     }
 }
+
+// Tag an archive is made from, `v1.0.tar.gz` is made from `v1.0`
+fn archive_tag(file: &str) -> Option<&str> {
+    [".tar.gz", ".zip"]
+        .iter()
+        .find_map(|extension| file.strip_suffix(extension))
+}
+
+// https://<host>/<owner>/<repo>/archive/<tag>.tar.gz, other `/archive/` directories
+// are plain file servers
+fn is_forge_archive(url: &str) -> bool {
+    let segments: Vec<&str> = match split_host(url) {
+        Some((_, path)) => path.split('/').filter(|s| !s.is_empty()).collect(),
+        None => return false,
+    };
+    match segments.as_slice() {
+        [_, _, "archive", file] => archive_tag(file).is_some(),
+        _ => false,
+    }
+}
+
+// Highest version among tags, the tag template strips prefixes like `v` or `release-`
+fn highest_tag(
+    source: &Source,
+    tag_template: &str,
+    tags: Vec<String>,
+) -> Result<Option<Update>, Box<dyn Error>> {
+    let tag_regex = source.version_regex(tag_template)?;
+    let mut latest: Option<String> = None;
+    for tag in tags {
+        // Tags which don't follow the template belong to something else
+        let version = match tag_regex.captures(&tag) {
+            Some(captures) => captures["version"].to_string(),
+            None => continue,
+        };
+        match latest {
+            Some(ref l) if vercmp(&version, l) != Ordering::Greater => {}
+            _ => latest = Some(version),
+        }
+    }
+
+    Ok(latest.map(|version| Update {
+        url: source.render(&source.url(), &version),
+        version,
+        source_arch: source.arch.clone(),
+        source_index: source.index,
+        checksum: None,
+    }))
+}
+
+// Splits `https://host/path` into `https://host` and `path`
+fn split_host(url: &str) -> Option<(&str, &str)> {
+    let host_start = url.find("://")? + 3;
+    let path_start = host_start + url[host_start..].find('/')?;
+    Some((&url[..path_start], &url[path_start + 1..]))
+}
+
+fn get_json<T: serde::de::DeserializeOwned>(url: &str) -> Result<T, Box<dyn Error>> {
+    let client = reqwest::blocking::Client::new();
+    let response = client.get(url).header("User-Agent", "pacops").send()?;
+    if !response.status().is_success() {
+        return Err(format!("{} responded with {}", url, response.status()).into());
+    }
+    Ok(response.json()?)
+}
+
+// Hex digests only, their length tells the algorithm apart but for b2 and sha512
+fn checksum_alg(configured: Option<&str>, checksum: &str) -> Result<HashAlg, Box<dyn Error>> {
+    if checksum.is_empty() || !checksum.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Checksum {} isn't a hex digest", checksum).into());
+    }
+    if let Some(alg) = configured {
+        return match HashAlg::from_name(alg) {
+            Some(alg) => Ok(alg),
+            None => Err(format!("Unknown checksum algorithm {}", alg).into()),
+        };
+    }
+    match checksum.len() {
+        32 => Ok(HashAlg::MD5),
+        40 => Ok(HashAlg::SHA1),
+        56 => Ok(HashAlg::SHA224),
+        64 => Ok(HashAlg::SHA256),
+        96 => Ok(HashAlg::SHA384),
+        128 => Ok(HashAlg::SHA512),
+        _ => Err(format!(
+            "Unable to guess algorithm of checksum {}, set checksum_alg",
+            checksum
+        )
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_directories_arent_forges() {
+        let guess = |url: &str| Remote::guess(url.to_string());
+        assert!(matches!(
+            guess("https://codeberg.org/owner/repo/archive/v${pkgver}.tar.gz"),
+            Remote::Gitea
+        ));
+        assert!(matches!(
+            guess("https://ftp.example.org/pub/archive/foo-${pkgver}.tar.gz"),
+            Remote::Listing
+        ));
+        assert!(matches!(
+            guess("https://ftp.example.org/archive/foo-${pkgver}.zip"),
+            Remote::Listing
+        ));
+    }
+}
@@ -0,0 +1,81 @@
+use serde::Deserialize;
+
+use std::collections::HashMap;
+use std::error::Error;
+
+use super::get_json;
+use crate::pkgbuild::HashAlg;
+use crate::settings::Settings;
+use crate::source::Source;
+use crate::update::Update;
+use crate::version::vercmp;
+
+#[derive(Deserialize, Debug)]
+struct Package {
+    #[serde(rename = "dist-tags", default)]
+    dist_tags: HashMap<String, String>,
+    #[serde(default)]
+    versions: HashMap<String, Version>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Version {
+    dist: Dist,
+}
+
+#[derive(Deserialize, Debug)]
+struct Dist {
+    // sha1 of the tarball
+    shasum: Option<String>,
+}
+
+// https://registry.npmjs.org/<pkg>/-/<pkg>-${pkgver}.tgz
+// https://registry.npmjs.org/@<scope>/<pkg>/-/<pkg>-${pkgver}.tgz
+pub fn latest(source: &Source, settings: &Settings) -> Result<Option<Update>, Box<dyn Error>> {
+    let url = source.template();
+    let name = url
+        .split("registry.npmjs.org/")
+        .nth(1)
+        .and_then(|path| path.split("/-/").next())
+        .filter(|name| !name.is_empty() && !name.contains('$'));
+    let name = match name {
+        Some(name) => name,
+        None => return Err(format!("Unable to find npm package name in {}", url).into()),
+    };
+
+    // Scoped packages are requested as `@scope%2Fname`
+    let package: Package = get_json(&format!(
+        "{}/{}",
+        settings.npm_registry(),
+        name.replace('/', "%2F")
+    ))?;
+    let version = if settings.npm_highest_version() {
+        package
+            .versions
+            .keys()
+            // Pre-releases have `-` which pkgver can't hold anyway
+            .filter(|version| !version.contains('-'))
+            .max_by(|a, b| vercmp(a, b))
+            .cloned()
+    } else {
+        // What `npm install` picks, a maintainer may tag a lower version as latest
+        package.dist_tags.get("latest").cloned()
+    };
+    let version = match version {
+        Some(version) => version,
+        None => return Ok(None),
+    };
+    let checksum = package
+        .versions
+        .get(&version)
+        .and_then(|v| v.dist.shasum.clone())
+        .map(|shasum| (HashAlg::SHA1, shasum));
+
+    Ok(Some(Update {
+        url: source.render(&url, &version),
+        version,
+        source_arch: source.arch.clone(),
+        source_index: source.index,
+        checksum,
+    }))
+}
@@ -0,0 +1,105 @@
+use regex::Regex;
+use serde::Deserialize;
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::error::Error;
+
+use super::get_json;
+use crate::pkgbuild::HashAlg;
+use crate::settings::Settings;
+use crate::source::Source;
+use crate::update::Update;
+use crate::version::vercmp;
+
+#[derive(Deserialize, Debug)]
+struct Project {
+    releases: HashMap<String, Vec<File>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct File {
+    filename: String,
+    url: String,
+    digests: Digests,
+    #[serde(default)]
+    yanked: bool,
+}
+
+#[derive(Deserialize, Debug)]
+struct Digests {
+    sha256: String,
+}
+
+// https://files.pythonhosted.org/packages/source/<p>/<project>/<project>-${pkgver}.tar.gz
+// https://pypi.io/packages/source/<p>/<project>/<project>-${pkgver}.tar.gz
+pub fn latest(source: &Source, settings: &Settings) -> Result<Option<Update>, Box<dyn Error>> {
+    let url = source.template();
+    let file_template = url.rsplit('/').next().unwrap();
+    let project = match project(&url, file_template) {
+        Some(project) => project,
+        None => return Err(format!("Unable to find PyPI project in {}", url).into()),
+    };
+
+    let releases =
+        get_json::<Project>(&format!("{}/pypi/{}/json", settings.pypi_api(), project))?.releases;
+    let mut latest: Option<&String> = None;
+    for (version, files) in &releases {
+        // Releases without files or with all of them yanked aren't installable
+        if files.iter().all(|file| file.yanked) || is_prerelease(version) {
+            continue;
+        }
+        match latest {
+            Some(l) if vercmp(version, l) != Ordering::Greater => {}
+            _ => latest = Some(version),
+        }
+    }
+    let version = match latest {
+        Some(version) => version,
+        None => return Ok(None),
+    };
+
+    // Published checksum is only of use for the file the PKGBUILD downloads
+    let filename = source.render(file_template, version);
+    let file = releases[version]
+        .iter()
+        .filter(|file| !file.yanked)
+        .find(|file| file.filename == filename);
+    Ok(Some(match file {
+        Some(file) => Update {
+            version: version.clone(),
+            source_arch: source.arch.clone(),
+            source_index: source.index,
+            url: file.url.clone(),
+            checksum: Some((HashAlg::SHA256, file.digests.sha256.clone())),
+        },
+        None => Update {
+            version: version.clone(),
+            source_arch: source.arch.clone(),
+            source_index: source.index,
+            url: source.render(&url, version),
+            checksum: None,
+        },
+    }))
+}
+
+// Project is a directory in `packages/source/`, or what precedes a version in a file name
+fn project(url: &str, file_template: &str) -> Option<String> {
+    if let Some(path) = url.split("/packages/source/").nth(1) {
+        let segments: Vec<&str> = path.split('/').collect();
+        if segments.len() == 3 && !segments[1].contains('$') {
+            return Some(segments[1].to_string());
+        }
+    }
+    let (project, _) = file_template.split_once("-${pkgver}")?;
+    if project.is_empty() || project.contains('$') {
+        return None;
+    }
+    Some(project.to_string())
+}
+
+// PEP 440 pre-releases and development releases, like 2.0a1, 2.0rc1 or 2.0.dev3
+fn is_prerelease(version: &str) -> bool {
+    let prerelease = Regex::new(r"(?i)\d[._-]?(a|b|c|rc|alpha|beta|pre|preview|dev)\d*").unwrap();
+    prerelease.is_match(version)
+}
@@ -0,0 +1,136 @@
+use flate2::read::GzDecoder;
+use roxmltree::{Document, Node};
+
+use std::cmp::Ordering;
+use std::error::Error;
+use std::io::Read;
+
+use crate::pkgbuild::HashAlg;
+use crate::source::Source;
+use crate::update::Update;
+use crate::version::vercmp;
+
+// How far up from a package to look for `repodata/`
+const MAX_DEPTH: usize = 4;
+
+// Entry of primary.xml, only the fields we need
+#[derive(Debug)]
+struct Package {
+    name: String,
+    arch: String,
+    location: String,
+    checksum: Option<(HashAlg, String)>,
+}
+
+// https://<repo>/[<dirs>/]<name>-${pkgver}-<release>.<arch>.rpm
+pub fn latest(source: &Source) -> Result<Option<Update>, Box<dyn Error>> {
+    let url = source.template();
+    let file_template = url.rsplit('/').next().unwrap();
+    let (name, arch) = match name_and_arch(file_template) {
+        Some(parts) => parts,
+        None => return Err(format!("Unable to find package name and arch in {}", url).into()),
+    };
+    let (repo, primary) = primary(&url)?;
+
+    let file_regex = source.version_regex(file_template)?;
+    let mut latest: Option<(String, Package)> = None;
+    for package in parse(&primary)?
+        .into_iter()
+        .filter(|package| package.name == name && package.arch == arch)
+    {
+        let file = package.location.rsplit('/').next().unwrap();
+        let version = match file_regex.captures(file) {
+            Some(captures) => captures["version"].to_string(),
+            None => continue,
+        };
+        match latest {
+            Some((ref l, _)) if vercmp(&version, l) != Ordering::Greater => {}
+            _ => latest = Some((version, package)),
+        }
+    }
+
+    Ok(latest.map(|(version, package)| Update {
+        version,
+        source_arch: source.arch.clone(),
+        source_index: source.index,
+        url: format!("{}/{}", repo, package.location),
+        checksum: package.checksum,
+    }))
+}
+
+// <name>-<version>-<release>.<arch>.rpm, name may have dashes on its own
+fn name_and_arch(file: &str) -> Option<(String, String)> {
+    let stem = file.strip_suffix(".rpm")?;
+    let (nvr, arch) = stem.rsplit_once('.')?;
+    let mut parts = nvr.rsplitn(3, '-');
+    let (_release, _version, name) = (parts.next()?, parts.next()?, parts.next()?);
+    if name.contains('$') || arch.contains('$') {
+        return None;
+    }
+    Some((name.to_string(), arch.to_string()))
+}
+
+// Walks up from the package directory to the repository root and reads its primary.xml
+fn primary(url: &str) -> Result<(String, String), Box<dyn Error>> {
+    let mut repo = url;
+    for _ in 0..MAX_DEPTH {
+        repo = match repo.rfind('/') {
+            Some(position) => &repo[..position],
+            None => break,
+        };
+        let response = reqwest::blocking::get(format!("{}/repodata/repomd.xml", repo))?;
+        if !response.status().is_success() {
+            continue;
+        }
+        let repomd = response.text()?;
+        let document = Document::parse(&repomd)?;
+        let location = document
+            .descendants()
+            .find(|node| node.has_tag_name("data") && node.attribute("type") == Some("primary"))
+            .and_then(|data| child(&data, "location"))
+            .and_then(|location| location.attribute("href"));
+        let location = match location {
+            Some(location) => location,
+            None => return Err(format!("{}/repodata/repomd.xml has no primary", repo).into()),
+        };
+
+        let content = reqwest::blocking::get(format!("{}/{}", repo, location))?.bytes()?;
+        let mut primary = String::new();
+        if location.ends_with(".gz") {
+            GzDecoder::new(&content[..]).read_to_string(&mut primary)?;
+        } else {
+            (&content[..]).read_to_string(&mut primary)?;
+        }
+        return Ok((repo.to_string(), primary));
+    }
+    Err(format!("Unable to find repodata/repomd.xml above {}", url).into())
+}
+
+fn parse(primary: &str) -> Result<Vec<Package>, Box<dyn Error>> {
+    let document = Document::parse(primary)?;
+    let packages = document
+        .root_element()
+        .children()
+        .filter(|node| node.has_tag_name("package"))
+        .filter_map(|package| {
+            let text = |tag| child(&package, tag).and_then(|node| node.text());
+            let checksum = child(&package, "checksum").and_then(|checksum| {
+                let alg = HashAlg::from_name(checksum.attribute("type")?)?;
+                Some((alg, checksum.text()?.to_string()))
+            });
+            Some(Package {
+                name: text("name")?.to_string(),
+                arch: text("arch")?.to_string(),
+                location: child(&package, "location")?.attribute("href")?.to_string(),
+                checksum,
+            })
+        })
+        .collect();
+    Ok(packages)
+}
+
+// First child element with a given name, namespace aside
+fn child<'a, 'input>(node: &Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children()
+        .find(|child| child.tag_name().name() == name)
+}
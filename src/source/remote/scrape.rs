@@ -0,0 +1,318 @@
+// Download pages, versions are whatever a configured regex finds on them
+
+use select::document::Document;
+use select::node::Node;
+use select::predicate::Class;
+
+use std::cmp::Ordering;
+use std::error::Error;
+
+use crate::source::Source;
+use crate::update::Update;
+use crate::version::vercmp;
+
+// One element of a selector, `a.download[href]` and alike
+#[derive(Default, Debug)]
+struct Compound {
+    name: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+    // Attribute with a value it has to equal
+    attrs: Vec<(String, Option<String>)>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+impl Compound {
+    fn matches(&self, node: &Node) -> bool {
+        node.name().is_some()
+            && self
+                .name
+                .as_ref()
+                .is_none_or(|name| node.name() == Some(name))
+            && self
+                .id
+                .as_ref()
+                .is_none_or(|id| node.attr("id") == Some(id))
+            && self
+                .classes
+                .iter()
+                .all(|class| node.is(Class(class.as_str())))
+            && self.attrs.iter().all(|(attr, value)| match value {
+                Some(value) => node.attr(attr) == Some(value),
+                None => node.attr(attr).is_some(),
+            })
+    }
+}
+
+// Subset of CSS selectors: names, `#id`, `.class`, `[attr]`, `[attr=value]`,
+// descendant and `>` child combinators. Anything else is refused, it would
+// silently match nothing otherwise.
+fn parse_selector(selector: &str) -> Result<Vec<(Combinator, Compound)>, Box<dyn Error>> {
+    let invalid = |why: &str| -> Box<dyn Error> {
+        format!("Unable to parse CSS selector {}: {}", selector, why).into()
+    };
+    let mut chars = selector.chars().peekable();
+    let mut parts = Vec::new();
+    let mut combinator = Combinator::Descendant;
+    loop {
+        let spaced = skip_spaces(&mut chars);
+        match chars.peek() {
+            None if parts.is_empty() => return Err(invalid("it's empty")),
+            None if combinator == Combinator::Child => {
+                return Err(invalid("`>` isn't followed by an element"))
+            }
+            None => return Ok(parts),
+            Some('>') => {
+                if parts.is_empty() || combinator == Combinator::Child {
+                    return Err(invalid("`>` isn't preceded by an element"));
+                }
+                chars.next();
+                combinator = Combinator::Child;
+                continue;
+            }
+            Some(_) if !parts.is_empty() && !spaced && combinator == Combinator::Descendant => {
+                return Err(invalid(&format!("unexpected `{}`", chars.peek().unwrap())));
+            }
+            Some(_) => {}
+        }
+
+        let mut compound = Compound::default();
+        let name = identifier(&mut chars);
+        let universal = name.is_empty() && chars.next_if_eq(&'*').is_some();
+        if !name.is_empty() {
+            compound.name = Some(name);
+        }
+        let mut selectors = 0;
+        while let Some(kind) = chars.next_if(|c| ['#', '.', '['].contains(c)) {
+            selectors += 1;
+            if kind == '[' {
+                compound
+                    .attrs
+                    .push(attribute(&mut chars).map_err(|why| invalid(&why))?);
+                continue;
+            }
+            let value = identifier(&mut chars);
+            if value.is_empty() {
+                return Err(invalid(&format!("`{}` isn't followed by a name", kind)));
+            }
+            match kind {
+                '#' => compound.id = Some(value),
+                _ => compound.classes.push(value),
+            }
+        }
+        if compound.name.is_none() && !universal && selectors == 0 {
+            let unexpected = chars.peek().unwrap();
+            return Err(invalid(&format!("`{}` isn't supported", unexpected)));
+        }
+        parts.push((combinator, compound));
+        combinator = Combinator::Descendant;
+    }
+}
+
+type Chars<'a> = std::iter::Peekable<std::str::Chars<'a>>;
+
+fn skip_spaces(chars: &mut Chars) -> bool {
+    let mut skipped = false;
+    while chars.next_if(|c| c.is_whitespace()).is_some() {
+        skipped = true;
+    }
+    skipped
+}
+
+// Element names, ids, classes and attribute names
+fn identifier(chars: &mut Chars) -> String {
+    let mut identifier = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '-' || *c == '_') {
+        identifier.push(c);
+    }
+    identifier
+}
+
+// `attr]` or `attr=value]`, the value may be quoted
+fn attribute(chars: &mut Chars) -> Result<(String, Option<String>), String> {
+    skip_spaces(chars);
+    let name = identifier(chars);
+    if name.is_empty() {
+        return Err(String::from("`[` isn't followed by an attribute name"));
+    }
+    skip_spaces(chars);
+    let value = match chars.next() {
+        Some(']') => return Ok((name, None)),
+        Some('=') => {
+            skip_spaces(chars);
+            match chars.next_if(|c| *c == '"' || *c == '\'') {
+                Some(quote) => {
+                    let mut value = String::new();
+                    loop {
+                        match chars.next() {
+                            Some(c) if c == quote => break,
+                            Some(c) => value.push(c),
+                            None => return Err(format!("value of {} is never closed", name)),
+                        }
+                    }
+                    value
+                }
+                None => identifier(chars),
+            }
+        }
+        Some(c) => return Err(format!("`{}` after {} isn't supported, only `=`", c, name)),
+        None => return Err(format!("`[{}` is never closed", name)),
+    };
+    skip_spaces(chars);
+    match chars.next() {
+        Some(']') => Ok((name, Some(value))),
+        Some(c) => Err(format!("unexpected `{}` in value of {}", c, name)),
+        None => Err(format!("`[{}` is never closed", name)),
+    }
+}
+
+// Matches right to left, ancestors of a node have to match preceding parts
+fn matches(node: &Node, parts: &[(Combinator, Compound)]) -> bool {
+    let ((combinator, compound), ancestors) = match parts.split_last() {
+        Some(last) => last,
+        None => return true,
+    };
+    if !compound.matches(node) {
+        return false;
+    }
+    if ancestors.is_empty() {
+        return true;
+    }
+    let mut parent = node.parent();
+    while let Some(node) = parent {
+        if matches(&node, ancestors) {
+            return true;
+        }
+        if *combinator == Combinator::Child {
+            return false;
+        }
+        parent = node.parent();
+    }
+    false
+}
+
+// Page comes from the configured URL, the regex is run on HTML of selected elements
+pub fn latest(source: &Source) -> Result<Option<Update>, Box<dyn Error>> {
+    let url = source.template();
+    let version_regex = source.version_regex(&url)?;
+    let page = reqwest::blocking::get(&url)?;
+    if !page.status().is_success() {
+        return Err(format!("{} responded with {}", url, page.status()).into());
+    }
+    let page = page.text()?;
+    let haystacks = match source.selector() {
+        Some(selector) => {
+            let parts = parse_selector(&selector)?;
+            Document::from(page.as_str())
+                .find(|node: &Node| matches(node, &parts))
+                .map(|node| node.html())
+                .collect()
+        }
+        None => vec![page],
+    };
+
+    let mut latest: Option<String> = None;
+    for haystack in &haystacks {
+        for captures in version_regex.captures_iter(haystack) {
+            let version = captures["version"].to_string();
+            match latest {
+                Some(ref l) if vercmp(&version, l) != Ordering::Greater => {}
+                _ => latest = Some(version),
+            }
+        }
+    }
+
+    Ok(latest.map(|version| Update {
+        url: source.render(&source.url(), &version),
+        version,
+        source_arch: source.arch.clone(),
+        source_index: source.index,
+        checksum: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<html><body>
+        <div id="downloads">
+          <ul class="stable list">
+            <li><a class="download" href="foo-1.0.tar.gz" title="Foo > 1.0">1.0</a></li>
+            <li><span><a class="download" href="foo-0.9.tar.gz">0.9</a></span></li>
+          </ul>
+        </div>
+        <a class="download" href="foo-2.0b1.tar.gz">2.0b1</a>
+        </body></html>"#;
+
+    fn hrefs(selector: &str) -> Vec<String> {
+        let parts = parse_selector(selector).unwrap();
+        Document::from(PAGE)
+            .find(|node: &Node| matches(node, &parts))
+            .map(|node| node.attr("href").unwrap_or_default().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn selectors_parse() {
+        let parts = parse_selector("div#downloads > ul.stable.list a[href][title = \"Foo > 1.0\"]")
+            .unwrap();
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0].0, Combinator::Descendant);
+        assert_eq!(parts[0].1.name.as_deref(), Some("div"));
+        assert_eq!(parts[0].1.id.as_deref(), Some("downloads"));
+        assert_eq!(parts[1].0, Combinator::Child);
+        assert_eq!(parts[1].1.classes, ["stable", "list"]);
+        assert_eq!(parts[2].0, Combinator::Descendant);
+        assert_eq!(
+            parts[2].1.attrs,
+            [
+                (String::from("href"), None),
+                (String::from("title"), Some(String::from("Foo > 1.0")))
+            ]
+        );
+        assert!(parse_selector("*.download").is_ok());
+    }
+
+    #[test]
+    fn unsupported_selectors_are_refused() {
+        for selector in [
+            "",
+            "a[href^=\"x\"]",
+            "a[href*=x]",
+            "a[title=Foo 1.0]",
+            "a[title=\"Foo",
+            "a[href",
+            "a:first-child",
+            "a, li",
+            "ul ~ a",
+            "> a",
+            "ul >",
+            "ul > > a",
+            "a.",
+        ] {
+            assert!(parse_selector(selector).is_err(), "{}", selector);
+        }
+    }
+
+    #[test]
+    fn selectors_match() {
+        assert_eq!(
+            hrefs("a.download"),
+            ["foo-1.0.tar.gz", "foo-0.9.tar.gz", "foo-2.0b1.tar.gz"]
+        );
+        assert_eq!(
+            hrefs("#downloads a.download"),
+            ["foo-1.0.tar.gz", "foo-0.9.tar.gz"]
+        );
+        assert_eq!(hrefs("ul > li > a"), ["foo-1.0.tar.gz"]);
+        assert_eq!(hrefs("a[title=\"Foo > 1.0\"]"), ["foo-1.0.tar.gz"]);
+        assert_eq!(hrefs("body > a[href]"), ["foo-2.0b1.tar.gz"]);
+        assert!(hrefs("ul.unstable a").is_empty());
+    }
+}
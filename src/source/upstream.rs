@@ -0,0 +1,203 @@
+// Upstreams configured per package for sources whose URL says nothing useful,
+// like a mirror or a CDN, in `.pacops.toml` next to the PKGBUILD:
+//
+//   [[upstream]]
+//   source = 0
+//   kind = "github-tag"
+//   url = "https://github.com/owner/repo/archive/v${pkgver}.tar.gz"
+//
+// or under `[packages.<pkgbase>]` of the main config. APT repositories of
+// different vendors name their suites differently, `suite` overrides `apt_suite`
+// of the main config for a `.deb` source. Pages without a better API
+// are scraped by `kind = "regex"` with a `regex` capturing `version`, optionally
+// narrowed down to elements matching a CSS `selector`. JSON endpoints are read by
+// `kind = "json"`, with JSONPaths of a version, and optionally of a download URL
+// and checksum:
+//
+//   [[upstream]]
+//   kind = "json"
+//   url = "https://example.org/api/latest.json"
+//   json_path = "$.version"
+//   url_path = "$.files[0].url"
+//   checksum_path = "$.files[0].sha256"
+//
+// Anything else is left to `kind = "command"`, a shell `command` run in the
+// PKGBUILD directory for at most `timeout` seconds, 60 by default. It prints
+// the version alone, `version=`, `url=`, `checksum=` lines or a JSON object
+// with those keys.
+
+use regex::Regex;
+use serde::Deserialize;
+
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use super::remote::Remote;
+use crate::pkgbuild::HashAlg;
+use crate::settings::Settings;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Upstream {
+    // Position in a source array, `source_<arch>` one if arch is set
+    #[serde(default)]
+    pub source: usize,
+    pub arch: Option<String>,
+    // Guessed from `url`, or from the source when both are omitted
+    pub kind: Option<Remote>,
+    // Where to look for versions instead of the source URL
+    pub url: Option<String>,
+    // Has to capture `version`, matched against tags, file names and alike
+    pub regex: Option<String>,
+    // JSONPath of a version in a JSON upstream's response
+    pub json_path: Option<String>,
+    // JSONPaths of a download URL and its checksum, the source URL is used without one
+    pub url_path: Option<String>,
+    pub checksum_path: Option<String>,
+    // Algorithm of the checksum, guessed from its length if omitted
+    pub checksum_alg: Option<String>,
+    // Elements of a page regex upstreams look into
+    pub selector: Option<String>,
+    pub tag_prefix: Option<String>,
+    // Suite of an APT repository, `apt_suite` of the main config is used without one
+    pub suite: Option<String>,
+    // Shell command of a command upstream and seconds it may run for
+    pub command: Option<String>,
+    pub timeout: Option<u64>,
+}
+
+impl Upstream {
+    pub fn remote(&self) -> Option<Remote> {
+        match (&self.kind, &self.url) {
+            (Some(kind), _) => Some(kind.clone()),
+            (None, Some(url)) => Some(Remote::guess(url.clone())),
+            (None, None) => None,
+        }
+    }
+
+    // Catches parameters which would be silently ignored
+    fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if let Some(regex) = &self.regex {
+            let regex = Regex::new(regex)?;
+            if !regex.capture_names().any(|name| name == Some("version")) {
+                return Err(format!(
+                    "Regex of source {} upstream doesn't capture `version`",
+                    self.source
+                )
+                .into());
+            }
+        }
+        let scraped = matches!(self.kind, Some(Remote::Regex));
+        if scraped && (self.url.is_none() || self.regex.is_none()) {
+            return Err(format!(
+                "Regex upstream of source {} needs both url and regex",
+                self.source
+            )
+            .into());
+        }
+        if !scraped && self.selector.is_some() {
+            return Err(format!(
+                "Upstream of source {} has selector, which only regex upstreams use",
+                self.source
+            )
+            .into());
+        }
+        let json = matches!(self.kind, Some(Remote::Json));
+        let command = matches!(self.kind, Some(Remote::Command));
+        if json && (self.url.is_none() || self.json_path.is_none()) {
+            return Err(format!(
+                "JSON upstream of source {} needs both url and json_path",
+                self.source
+            )
+            .into());
+        }
+        let json_options = [
+            ("json_path", &self.json_path),
+            ("url_path", &self.url_path),
+            ("checksum_path", &self.checksum_path),
+        ];
+        for (option, value) in &json_options {
+            if !json && value.is_some() {
+                return Err(format!(
+                    "Upstream of source {} has {}, which only JSON upstreams use",
+                    self.source, option
+                )
+                .into());
+            }
+        }
+        if command && (self.command.is_none() || self.url.is_some()) {
+            return Err(format!(
+                "Command upstream of source {} needs a command and no url",
+                self.source
+            )
+            .into());
+        }
+        if !command && (self.command.is_some() || self.timeout.is_some()) {
+            return Err(format!(
+                "Upstream of source {} has command or timeout, which only command upstreams use",
+                self.source
+            )
+            .into());
+        }
+        if !json && !command && self.checksum_alg.is_some() {
+            return Err(format!(
+                "Upstream of source {} has checksum_alg, which only JSON and command upstreams use",
+                self.source
+            )
+            .into());
+        }
+        // Without kind and url, the source itself may well be a `.deb`
+        if self.suite.is_some() && !matches!(self.remote(), Some(Remote::Deb) | None) {
+            return Err(format!(
+                "Upstream of source {} has suite, which only deb upstreams use",
+                self.source
+            )
+            .into());
+        }
+        if let Some(alg) = &self.checksum_alg {
+            if HashAlg::from_name(alg).is_none() {
+                return Err(format!("Unknown checksum algorithm {}", alg).into());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Package {
+    #[serde(default)]
+    pub upstream: Vec<Upstream>,
+}
+
+// Upstreams of a package, `.pacops.toml` overrides the main config for the same source
+pub fn load(
+    pkgbuild_path: &Path,
+    pkgbase: &str,
+    settings: &Settings,
+) -> Result<Vec<Upstream>, Box<dyn Error>> {
+    let local_path = pkgbuild_path.parent().unwrap().join(".pacops.toml");
+    let local = if local_path.exists() {
+        match toml::from_str::<Package>(&fs::read_to_string(&local_path)?) {
+            Ok(package) => package.upstream,
+            Err(why) => {
+                return Err(format!("Unable to parse {}: {}", local_path.display(), why).into())
+            }
+        }
+    } else {
+        Vec::new()
+    };
+
+    let mut upstreams = local.clone();
+    for upstream in settings.package(pkgbase).upstream {
+        if !local
+            .iter()
+            .any(|u| u.source == upstream.source && u.arch == upstream.arch)
+        {
+            upstreams.push(upstream);
+        }
+    }
+    for upstream in &upstreams {
+        upstream.validate()?;
+    }
+    Ok(upstreams)
+}
@@ -0,0 +1,638 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::str;
+use std::str::FromStr;
+
+use crate::pkgbuild::{self, Pkgbuild};
+
+// Checksum arrays in the order makepkg writes them
+const HASH_ARRAYS: [&str; 8] = [
+    "cksums",
+    "md5sums",
+    "sha1sums",
+    "sha224sums",
+    "sha256sums",
+    "sha384sums",
+    "sha512sums",
+    "b2sums",
+];
+
+// Attributes which may have `_<arch>` variants
+const ARCH_ATTRIBUTES: [&str; 8] = [
+    "source",
+    "provides",
+    "conflicts",
+    "depends",
+    "replaces",
+    "optdepends",
+    "makedepends",
+    "checkdepends",
+];
+
+// Attributes which AUR and makepkg care about staying in sync, `_<arch>` variants included
+const LINTED: [&str; 7] = [
+    "pkgver",
+    "pkgrel",
+    "epoch",
+    "source",
+    "depends",
+    "makedepends",
+    "checkdepends",
+];
+
+const PKGBASE_SINGLEVALUED: [&str; 7] = [
+    "pkgdesc",
+    "pkgver",
+    "pkgrel",
+    "epoch",
+    "url",
+    "install",
+    "changelog",
+];
+
+const PKGBASE_MULTIVALUED: [&str; 15] = [
+    "arch",
+    "groups",
+    "license",
+    "checkdepends",
+    "makedepends",
+    "depends",
+    "optdepends",
+    "provides",
+    "conflicts",
+    "replaces",
+    "noextract",
+    "options",
+    "backup",
+    "source",
+    "validpgpkeys",
+];
+
+const PKGNAME_SINGLEVALUED: [&str; 4] = ["pkgdesc", "url", "install", "changelog"];
+
+const PKGNAME_MULTIVALUED: [&str; 11] = [
+    "arch",
+    "groups",
+    "license",
+    "checkdepends",
+    "depends",
+    "optdepends",
+    "provides",
+    "conflicts",
+    "replaces",
+    "options",
+    "backup",
+];
+
+// Contents of a .SRCINFO file
+#[derive(Clone, Debug, PartialEq)]
+pub struct Srcinfo {
+    pub pkgbase: Section,
+    // One per pkgname, holding only what package functions override
+    pub packages: Vec<Section>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Section {
+    pub name: String,
+    pub fields: Vec<(String, String)>,
+}
+
+impl Section {
+    fn new(name: String) -> Section {
+        Section {
+            name,
+            fields: Vec::new(),
+        }
+    }
+
+    // Values of an attribute, in order
+    pub fn values(&self, attribute: &str) -> Vec<&str> {
+        self.fields
+            .iter()
+            .filter(|(name, _)| name == attribute)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    // Attribute names, in order of first appearance
+    pub fn attributes(&self) -> Vec<&str> {
+        let mut attributes: Vec<&str> = Vec::new();
+        for (name, _) in &self.fields {
+            if !attributes.contains(&name.as_str()) {
+                attributes.push(name);
+            }
+        }
+        attributes
+    }
+
+    // Adds a line per value, or an empty one for an empty array
+    fn add(&mut self, attribute: &str, values: &[String]) {
+        if values.is_empty() {
+            self.fields.push((attribute.to_string(), String::new()));
+        }
+        for value in values {
+            let value = value.split_whitespace().collect::<Vec<&str>>().join(" ");
+            self.fields.push((attribute.to_string(), value));
+        }
+    }
+}
+
+impl Srcinfo {
+    // Mirrors what `makepkg --printsrcinfo` writes
+    pub fn from_pkgbuild(pkgbuild: &Pkgbuild) -> Srcinfo {
+        let mut pkgbase = Section::new(pkgbuild.pkgbase().clone());
+        let global = |attribute: &str| pkgbuild.variable(attribute).cloned();
+        let arches = global("arch").unwrap_or_default();
+        for (attribute, multivalued) in attributes(&PKGBASE_SINGLEVALUED, &PKGBASE_MULTIVALUED)
+            .into_iter()
+            .chain(arch_attributes(&arches))
+        {
+            if let Some(values) = global(&attribute) {
+                // Empty global attributes aren't written
+                if values.is_empty() || (values.len() == 1 && values[0].is_empty()) {
+                    continue;
+                }
+                pkgbase.add(&attribute, single(&values, multivalued));
+            }
+        }
+
+        let mut packages = Vec::new();
+        for pkgname in pkgbuild.pkgnames() {
+            let mut package = Section::new(pkgname.clone());
+            let overrides = pkgbuild.package_variables(pkgname);
+            let arches = overrides
+                .get("arch")
+                .cloned()
+                .unwrap_or_else(|| arches.clone());
+            for (attribute, multivalued) in attributes(&PKGNAME_SINGLEVALUED, &PKGNAME_MULTIVALUED)
+                .into_iter()
+                .chain(arch_attributes(&arches))
+            {
+                if let Some(values) = overrides.get(&attribute) {
+                    package.add(&attribute, single(values, multivalued));
+                }
+            }
+            packages.push(package);
+        }
+
+        Srcinfo { pkgbase, packages }
+    }
+
+    // Linted attributes of `self` which differ from `expected`
+    pub fn mismatches(&self, expected: &Srcinfo) -> Vec<Mismatch> {
+        let mut mismatches = section_mismatches("pkgbase", &self.pkgbase, &expected.pkgbase);
+        for package in &expected.packages {
+            match self.packages.iter().find(|p| p.name == package.name) {
+                Some(committed) => {
+                    mismatches.append(&mut section_mismatches("pkgname", committed, package))
+                }
+                None => mismatches.push(Mismatch::new(
+                    "pkgname",
+                    &package.name,
+                    "pkgname",
+                    &[],
+                    &[&package.name],
+                )),
+            }
+        }
+        for package in &self.packages {
+            if !expected.packages.iter().any(|p| p.name == package.name) {
+                mismatches.push(Mismatch::new(
+                    "pkgname",
+                    &package.name,
+                    "pkgname",
+                    &[&package.name],
+                    &[],
+                ));
+            }
+        }
+        mismatches
+    }
+}
+
+impl FromStr for Srcinfo {
+    type Err = Box<dyn Error>;
+
+    // Parses `key = value` lines, `pkgbase` and `pkgname` ones start sections
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut pkgbase: Option<Section> = None;
+        let mut packages: Vec<Section> = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim().to_string()),
+                None => {
+                    return Err(format!("Malformed .SRCINFO line {}: {}", number + 1, line).into())
+                }
+            };
+            match (key, &mut pkgbase, packages.last_mut()) {
+                ("pkgbase", None, _) => pkgbase = Some(Section::new(value)),
+                ("pkgname", Some(_), _) => packages.push(Section::new(value)),
+                (_, Some(_), Some(package)) => package.fields.push((key.to_string(), value)),
+                (_, Some(pkgbase), None) => pkgbase.fields.push((key.to_string(), value)),
+                _ => {
+                    return Err(format!(
+                        "Unexpected .SRCINFO line {}, it must start with pkgbase: {}",
+                        number + 1,
+                        line
+                    )
+                    .into())
+                }
+            }
+        }
+        match pkgbase {
+            Some(pkgbase) => Ok(Srcinfo { pkgbase, packages }),
+            None => {
+                let error: Box<dyn Error> = String::from("No pkgbase in .SRCINFO").into();
+                Err(error)
+            }
+        }
+    }
+}
+
+// A linted attribute whose .SRCINFO values aren't what the PKGBUILD gives
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mismatch {
+    // `pkgbase` or `pkgname`, and its value
+    pub kind: String,
+    pub section: String,
+    pub attribute: String,
+    pub committed: Vec<String>,
+    pub expected: Vec<String>,
+}
+
+impl Mismatch {
+    fn new(
+        kind: &str,
+        section: &str,
+        attribute: &str,
+        committed: &[&str],
+        expected: &[&str],
+    ) -> Mismatch {
+        Mismatch {
+            kind: kind.to_string(),
+            section: section.to_string(),
+            attribute: attribute.to_string(),
+            committed: committed.iter().map(|value| value.to_string()).collect(),
+            expected: expected.iter().map(|value| value.to_string()).collect(),
+        }
+    }
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let show = |values: &[String]| {
+            if values.is_empty() {
+                String::from("(none)")
+            } else {
+                values.join(", ")
+            }
+        };
+        write!(
+            f,
+            "{} {}: {} is {} in .SRCINFO but {} in PKGBUILD",
+            self.kind,
+            self.section,
+            self.attribute,
+            show(&self.committed),
+            show(&self.expected)
+        )
+    }
+}
+
+fn section_mismatches(kind: &str, committed: &Section, expected: &Section) -> Vec<Mismatch> {
+    let mut attributes = expected.attributes();
+    for attribute in committed.attributes() {
+        if !attributes.contains(&attribute) {
+            attributes.push(attribute);
+        }
+    }
+    attributes
+        .into_iter()
+        .filter(|attribute| linted(attribute))
+        .filter(|attribute| committed.values(attribute) != expected.values(attribute))
+        .map(|attribute| {
+            Mismatch::new(
+                kind,
+                &committed.name,
+                attribute,
+                &committed.values(attribute),
+                &expected.values(attribute),
+            )
+        })
+        .collect()
+}
+
+fn linted(attribute: &str) -> bool {
+    let name = attribute.split('_').next().unwrap();
+    LINTED.contains(&name) || HASH_ARRAYS.contains(&name)
+}
+
+// Compares .SRCINFO next to a PKGBUILD with what it should contain, mismatches of
+// attributes computed by the shell come second as we can't tell what they should be
+pub fn lint(pkgbuild_path: &Path) -> Result<(Vec<Mismatch>, Vec<Mismatch>), Box<dyn Error>> {
+    let srcinfo_path = pkgbuild::srcinfo_path(pkgbuild_path)?;
+    let committed = match fs::read_to_string(&srcinfo_path) {
+        Ok(text) => text.parse::<Srcinfo>()?,
+        Err(why) => {
+            return Err(format!("Unable to read {}: {}", srcinfo_path.display(), why).into())
+        }
+    };
+    let pkgbuild = Pkgbuild::from_file(pkgbuild_path.to_str().unwrap())?;
+    let pkgbuild = pkgbuild.borrow();
+    let expected = Srcinfo::from_pkgbuild(&pkgbuild);
+    Ok(committed
+        .mismatches(&expected)
+        .into_iter()
+        .partition(|mismatch| {
+            let pkgname = match mismatch.kind.as_str() {
+                "pkgname" => Some(mismatch.section.as_str()),
+                _ => None,
+            };
+            !pkgbuild.is_opaque(&mismatch.attribute, pkgname)
+        }))
+}
+
+impl fmt::Display for Srcinfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_section(f, "pkgbase", &self.pkgbase)?;
+        for package in &self.packages {
+            write_section(f, "pkgname", package)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_section(f: &mut fmt::Formatter, kind: &str, section: &Section) -> fmt::Result {
+    writeln!(f, "{} = {}", kind, section.name)?;
+    for (attribute, value) in &section.fields {
+        writeln!(f, "\t{} = {}", attribute, value)?;
+    }
+    writeln!(f)
+}
+
+// Attribute names paired with whether they're arrays
+fn attributes(singlevalued: &[&str], multivalued: &[&str]) -> Vec<(String, bool)> {
+    singlevalued
+        .iter()
+        .map(|attribute| (attribute.to_string(), false))
+        .chain(
+            multivalued
+                .iter()
+                .chain(HASH_ARRAYS.iter())
+                .map(|attribute| (attribute.to_string(), true)),
+        )
+        .collect()
+}
+
+fn arch_attributes(arches: &[String]) -> Vec<(String, bool)> {
+    arches
+        .iter()
+        // There's no such thing as depends_any
+        .filter(|arch| arch.as_str() != "any")
+        .flat_map(|arch| {
+            ARCH_ATTRIBUTES
+                .iter()
+                .chain(HASH_ARRAYS.iter())
+                .map(move |attribute| (format!("{}_{}", attribute, arch), true))
+        })
+        .collect()
+}
+
+fn single(values: &[String], multivalued: bool) -> &[String] {
+    if multivalued || values.is_empty() {
+        values
+    } else {
+        &values[..1]
+    }
+}
+
+// .SRCINFO as makepkg generates it, for comparison
+pub fn makepkg(pkgbuild_dir: &Path) -> Result<String, Box<dyn Error>> {
+    let mkpkg = match Command::new("makepkg")
+        .current_dir(pkgbuild_dir.to_str().unwrap())
+        .arg("--printsrcinfo")
+        .output()
+    {
+        Ok(mkpkg) => mkpkg,
+        Err(why) => return Err(format!("Unable to run makepkg: {}", why).into()),
+    };
+    if mkpkg.status.success() {
+        return Ok(str::from_utf8(&mkpkg.stdout)?.to_string());
+    }
+
+    let error: Box<dyn std::error::Error> = format!(
+        "Unable to generate .SRCINFO:\n {}",
+        str::from_utf8(&mkpkg.stderr).unwrap()
+    )
+    .into();
+    Err(error)
+}
+
+// Lines which differ between two texts, `-` for the old one and `+` for the new one
+pub fn diff(old: &str, new: &str) -> Vec<String> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    // Longest common subsequence lengths for every pair of suffixes
+    let mut lcs = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            lines.push(format!("-{}", old[i]));
+            i += 1;
+        } else {
+            lines.push(format!("+{}", new[j]));
+            j += 1;
+        }
+    }
+    lines.extend(old[i..].iter().map(|line| format!("-{}", line)));
+    lines.extend(new[j..].iter().map(|line| format!("+{}", line)));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PKGBUILD: &str = r#"pkgbase=foo
+pkgname=(foo foo-docs)
+pkgver=1.0
+pkgrel=2
+pkgdesc="Foo tool"
+arch=(x86_64 aarch64)
+url="https://e.org"
+license=(MIT)
+depends=(glibc)
+makedepends=(cmake)
+source=("https://e.org/foo-$pkgver.tar.gz")
+source_x86_64=("https://e.org/foo-bin-$pkgver-x86_64.tar.gz")
+source_aarch64=("https://e.org/foo-bin-$pkgver-aarch64.tar.gz")
+sha256sums=('aa')
+sha256sums_x86_64=('bb')
+sha256sums_aarch64=('cc')
+
+package_foo() {
+  depends+=(zlib)
+  depends_x86_64=(lib32-glibc)
+  install -Dm755 foo "$pkgdir/usr/bin/foo"
+}
+
+package_foo-docs() {
+  pkgdesc="Documentation of foo"
+  arch=(any)
+  depends=()
+}
+"#;
+
+    const SRCINFO: &str = "pkgbase = foo
+\tpkgdesc = Foo tool
+\tpkgver = 1.0
+\tpkgrel = 2
+\turl = https://e.org
+\tarch = x86_64
+\tarch = aarch64
+\tlicense = MIT
+\tmakedepends = cmake
+\tdepends = glibc
+\tsource = https://e.org/foo-1.0.tar.gz
+\tsha256sums = aa
+\tsource_x86_64 = https://e.org/foo-bin-1.0-x86_64.tar.gz
+\tsha256sums_x86_64 = bb
+\tsource_aarch64 = https://e.org/foo-bin-1.0-aarch64.tar.gz
+\tsha256sums_aarch64 = cc
+
+pkgname = foo
+\tdepends = glibc
+\tdepends = zlib
+\tdepends_x86_64 = lib32-glibc
+
+pkgname = foo-docs
+\tpkgdesc = Documentation of foo
+\tarch = any
+\tdepends = 
+
+";
+
+    fn srcinfo(pkgbuild: &str) -> Srcinfo {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("PKGBUILD");
+        fs::write(&path, pkgbuild).unwrap();
+        let pkgbuild = Pkgbuild::from_file(path.to_str().unwrap()).unwrap();
+        let srcinfo = Srcinfo::from_pkgbuild(&pkgbuild.borrow());
+        srcinfo
+    }
+
+    #[test]
+    fn split_packages_with_arch_arrays() {
+        assert_eq!(srcinfo(PKGBUILD).to_string(), SRCINFO);
+    }
+
+    #[test]
+    fn packages_without_overrides_have_empty_sections() {
+        let srcinfo = srcinfo("pkgname=bar\npkgver=1\npkgrel=1\narch=(any)\ndepends=()\n");
+        assert_eq!(
+            srcinfo.to_string(),
+            "pkgbase = bar\n\tpkgver = 1\n\tpkgrel = 1\n\tarch = any\n\npkgname = bar\n\n"
+        );
+    }
+
+    #[test]
+    fn diff_marks_changed_lines() {
+        assert_eq!(diff("a\nb\nc\nd\n", "a\nc\nd\ne\n"), ["-b", "+e"]);
+        assert_eq!(diff("a\nb\n", "b\na\n"), ["-a", "+a"]);
+        assert!(diff(SRCINFO, SRCINFO).is_empty());
+    }
+
+    #[test]
+    fn parsed_srcinfo_round_trips() {
+        let parsed = SRCINFO.parse::<Srcinfo>().unwrap();
+        assert_eq!(parsed, srcinfo(PKGBUILD));
+        assert_eq!(parsed.to_string(), SRCINFO);
+        assert!(parsed.mismatches(&srcinfo(PKGBUILD)).is_empty());
+    }
+
+    #[test]
+    fn malformed_srcinfo_is_an_error() {
+        assert!("pkgver = 1\n".parse::<Srcinfo>().is_err());
+        assert!("pkgbase = foo\n\tpkgver\n".parse::<Srcinfo>().is_err());
+        assert!("# nothing\n".parse::<Srcinfo>().is_err());
+    }
+
+    #[test]
+    fn stale_pkgver_and_checksums_mismatch() {
+        let stale = SRCINFO
+            .replace("pkgver = 1.0", "pkgver = 0.9")
+            .replace("foo-1.0.tar.gz", "foo-0.9.tar.gz")
+            .replace("sha256sums_x86_64 = bb", "sha256sums_x86_64 = 00")
+            // Descriptions aren't linted
+            .replace("pkgdesc = Foo tool", "pkgdesc = Old");
+        let mismatches = stale
+            .parse::<Srcinfo>()
+            .unwrap()
+            .mismatches(&srcinfo(PKGBUILD));
+        let attributes: Vec<&str> = mismatches
+            .iter()
+            .map(|mismatch| mismatch.attribute.as_str())
+            .collect();
+        assert_eq!(attributes, ["pkgver", "source", "sha256sums_x86_64"]);
+        assert_eq!(
+            mismatches[0].to_string(),
+            "pkgbase foo: pkgver is 0.9 in .SRCINFO but 1.0 in PKGBUILD"
+        );
+    }
+
+    #[test]
+    fn missing_packages_mismatch() {
+        let srcinfo = srcinfo(PKGBUILD);
+        let mut committed = srcinfo.clone();
+        committed.packages.pop();
+        let mismatches = committed.mismatches(&srcinfo);
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(
+            mismatches[0].to_string(),
+            "pkgname foo-docs: pkgname is (none) in .SRCINFO but foo-docs in PKGBUILD"
+        );
+    }
+
+    #[test]
+    fn attributes_computed_by_the_shell_are_unchecked() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("PKGBUILD");
+        fs::write(
+            &path,
+            "pkgname=foo-bin\n_pkgname=${pkgname%-bin}\npkgver=1\npkgrel=1\narch=(x86_64)\nsource=(\"https://e.org/${_pkgname}-$pkgver.tar.gz\")\nsha256sums=('aa')\n",
+        )
+        .unwrap();
+        fs::write(
+            directory.path().join(".SRCINFO"),
+            "pkgbase = foo-bin\n\tpkgver = 0\n\tpkgrel = 1\n\tarch = x86_64\n\tsource = https://e.org/foo-1.tar.gz\n\tsha256sums = aa\n\npkgname = foo-bin\n",
+        )
+        .unwrap();
+        let (mismatches, unchecked) = lint(&path).unwrap();
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].attribute, "pkgver");
+        assert_eq!(unchecked.len(), 1);
+        assert_eq!(unchecked[0].attribute, "source");
+    }
+}
@@ -3,7 +3,7 @@ use crate::pkgbuild::HashAlg;
 use std::error::Error;
 use std::fs::File;
 
-use blake2::{Blake2b, Blake2s, Digest};
+use blake2::{Blake2b, Digest};
 use md5::Md5;
 use sha1::Sha1;
 use sha2::{Sha224, Sha256, Sha384, Sha512};
@@ -12,21 +12,32 @@ use tempfile::Builder;
 #[derive(Clone)]
 pub struct Update {
     pub version: String,
+    pub source_arch: Option<String>,
     pub source_index: usize,
     pub url: String,
+    // Checksum published by upstream along with the file
+    pub checksum: Option<(HashAlg, String)>,
 }
 
 impl Update {
-    pub fn hash(&self, hash_alg: HashAlg) -> Result<String, Box<dyn Error>> {
+    // Downloads the new source once and computes a checksum for every algorithm
+    pub fn hashes(&self, hash_algs: &[HashAlg]) -> Result<Vec<String>, Box<dyn Error>> {
+        // No need to download anything when upstream already told the checksum
+        if let Some((published_alg, hash)) = &self.checksum {
+            if hash_algs.iter().all(|hash_alg| hash_alg == published_alg) {
+                println!("Using {} published upstream", published_alg.name());
+                return Ok(vec![hash.clone(); hash_algs.len()]);
+            }
+        }
         let tmp_dir = Builder::new().prefix("example").tempdir()?;
         println!("New source: '{}'", &self.url);
         let client = reqwest::blocking::Client::builder().timeout(None).build()?;
-        let resp = client.get(&self.url).send()?;
+        let resp = client.get(&self.url).send()?.error_for_status()?;
         let _dest = {
             let fname = resp
                 .url()
                 .path_segments()
-                .and_then(|segments| segments.last())
+                .and_then(|mut segments| segments.next_back())
                 .and_then(|name| if name.is_empty() { None } else { Some(name) })
                 .unwrap_or("tmp.bin");
 
@@ -37,42 +48,49 @@ impl Update {
         };
         let content = resp.bytes()?;
         //copy(&mut content.as_bytes(), &mut dest)?;
-        match hash_alg {
-            HashAlg::B2 => {
-                let mut hasher = Blake2b::new();
-                hasher.update(content);
-                Ok(format!("{:x}", hasher.finalize()))
-            }
-            HashAlg::SHA1 => {
-                let mut hasher = Sha1::new();
-                hasher.update(content);
-                Ok(format!("{:x}", hasher.finalize()))
-            }
-            HashAlg::SHA224 => {
-                let mut hasher = Sha224::new();
-                hasher.update(content);
-                Ok(format!("{:x}", hasher.finalize()))
-            }
-            HashAlg::SHA256 => {
-                let mut hasher = Sha256::new();
-                hasher.update(content);
-                Ok(format!("{:x}", hasher.finalize()))
-            }
-            HashAlg::SHA384 => {
-                let mut hasher = Sha384::new();
-                hasher.update(content);
-                Ok(format!("{:x}", hasher.finalize()))
-            }
-            HashAlg::SHA512 => {
-                let mut hasher = Sha512::new();
-                hasher.update(content);
-                Ok(format!("{:x}", hasher.finalize()))
-            }
-            HashAlg::MD5 => {
-                let mut hasher = Md5::new();
-                hasher.update(content);
-                Ok(format!("{:x}", hasher.finalize()))
-            }
+        Ok(hash_algs
+            .iter()
+            .map(|hash_alg| digest(*hash_alg, &content))
+            .collect())
+    }
+}
+
+fn digest(hash_alg: HashAlg, content: &[u8]) -> String {
+    match hash_alg {
+        HashAlg::B2 => {
+            let mut hasher = Blake2b::new();
+            hasher.update(content);
+            format!("{:x}", hasher.finalize())
+        }
+        HashAlg::SHA1 => {
+            let mut hasher = Sha1::new();
+            hasher.update(content);
+            format!("{:x}", hasher.finalize())
+        }
+        HashAlg::SHA224 => {
+            let mut hasher = Sha224::new();
+            hasher.update(content);
+            format!("{:x}", hasher.finalize())
+        }
+        HashAlg::SHA256 => {
+            let mut hasher = Sha256::new();
+            hasher.update(content);
+            format!("{:x}", hasher.finalize())
+        }
+        HashAlg::SHA384 => {
+            let mut hasher = Sha384::new();
+            hasher.update(content);
+            format!("{:x}", hasher.finalize())
+        }
+        HashAlg::SHA512 => {
+            let mut hasher = Sha512::new();
+            hasher.update(content);
+            format!("{:x}", hasher.finalize())
+        }
+        HashAlg::MD5 => {
+            let mut hasher = Md5::new();
+            hasher.update(content);
+            format!("{:x}", hasher.finalize())
         }
     }
 }
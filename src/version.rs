@@ -0,0 +1,275 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+// Package version the way pacman sees it: [epoch:]pkgver[-pkgrel]
+#[derive(Clone, Debug)]
+pub struct PkgVersion {
+    pub epoch: Option<String>,
+    pub pkgver: String,
+    pub pkgrel: Option<String>,
+}
+
+impl PkgVersion {
+    pub fn new(epoch: Option<String>, pkgver: String, pkgrel: Option<String>) -> PkgVersion {
+        PkgVersion {
+            epoch,
+            pkgver,
+            pkgrel,
+        }
+    }
+
+    // Same as parseEVR() in libalpm
+    pub fn parse(version: &str) -> PkgVersion {
+        let digits = version.bytes().take_while(u8::is_ascii_digit).count();
+        let (epoch, rest) = match version[digits..].strip_prefix(':') {
+            Some(rest) => (Some(version[..digits].to_string()), rest),
+            None => (None, version),
+        };
+        match rest.rfind('-') {
+            Some(position) => PkgVersion::new(
+                epoch,
+                rest[..position].to_string(),
+                Some(rest[position + 1..].to_string()),
+            ),
+            None => PkgVersion::new(epoch, rest.to_string(), None),
+        }
+    }
+
+    fn epoch(&self) -> &str {
+        match self.epoch.as_deref() {
+            Some("") | None => "0",
+            Some(epoch) => epoch,
+        }
+    }
+}
+
+impl FromStr for PkgVersion {
+    type Err = std::convert::Infallible;
+
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        Ok(PkgVersion::parse(version))
+    }
+}
+
+impl fmt::Display for PkgVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(epoch) = &self.epoch {
+            write!(f, "{}:", epoch)?;
+        }
+        write!(f, "{}", self.pkgver)?;
+        if let Some(pkgrel) = &self.pkgrel {
+            write!(f, "-{}", pkgrel)?;
+        }
+        Ok(())
+    }
+}
+
+// Same as alpm_pkg_vercmp(), pkgrel only matters when both sides have it
+impl Ord for PkgVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        rpmvercmp(self.epoch(), other.epoch())
+            .then_with(|| rpmvercmp(&self.pkgver, &other.pkgver))
+            .then_with(|| match (&self.pkgrel, &other.pkgrel) {
+                (Some(pkgrel), Some(other_pkgrel)) => rpmvercmp(pkgrel, other_pkgrel),
+                _ => Ordering::Equal,
+            })
+    }
+}
+
+impl PartialOrd for PkgVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for PkgVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for PkgVersion {}
+
+// Compares two version strings like `vercmp` does
+pub fn vercmp(a: &str, b: &str) -> Ordering {
+    PkgVersion::parse(a).cmp(&PkgVersion::parse(b))
+}
+
+// Port of rpmvercmp() from libalpm/version.c
+fn rpmvercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+    let one = a.as_bytes();
+    let two = b.as_bytes();
+    // Ends of the previous segments
+    let (mut ptr1, mut ptr2) = (0, 0);
+    // Starts of the current segments
+    let (mut i, mut j) = (0, 0);
+
+    while i < one.len() && j < two.len() {
+        while i < one.len() && !one[i].is_ascii_alphanumeric() {
+            i += 1;
+        }
+        while j < two.len() && !two[j].is_ascii_alphanumeric() {
+            j += 1;
+        }
+
+        if i >= one.len() || j >= two.len() {
+            break;
+        }
+
+        // Separators of different lengths decide on their own
+        if i - ptr1 != j - ptr2 {
+            return (i - ptr1).cmp(&(j - ptr2));
+        }
+
+        ptr1 = i;
+        ptr2 = j;
+
+        // Grab the first completely numeric or completely alpha segment
+        let is_num = one[ptr1].is_ascii_digit();
+        let same_kind = |c: &u8| {
+            if is_num {
+                c.is_ascii_digit()
+            } else {
+                c.is_ascii_alphabetic()
+            }
+        };
+        ptr1 += one[ptr1..].iter().take_while(|c| same_kind(c)).count();
+        ptr2 += two[ptr2..].iter().take_while(|c| same_kind(c)).count();
+
+        // Numeric segments are always newer than alpha ones
+        if j == ptr2 {
+            return if is_num {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            };
+        }
+
+        let mut segment1 = &one[i..ptr1];
+        let mut segment2 = &two[j..ptr2];
+        if is_num {
+            // Leading zeros don't count, then the longer number wins
+            while segment1.first() == Some(&b'0') {
+                segment1 = &segment1[1..];
+            }
+            while segment2.first() == Some(&b'0') {
+                segment2 = &segment2[1..];
+            }
+            match segment1.len().cmp(&segment2.len()) {
+                Ordering::Equal => {}
+                ordering => return ordering,
+            }
+        }
+        match segment1.cmp(segment2) {
+            Ordering::Equal => {}
+            ordering => return ordering,
+        }
+
+        i = ptr1;
+        j = ptr2;
+    }
+
+    // All segments compared equal, only separators were different
+    if i >= one.len() && j >= two.len() {
+        return Ordering::Equal;
+    }
+
+    // A remaining alpha string never beats an empty one:
+    // - if one is empty and two isn't alpha, two is newer
+    // - if one is alpha, two is newer
+    // - otherwise one is newer
+    let one_rest = one.get(i);
+    let two_rest = two.get(j);
+    if (one_rest.is_none() && !two_rest.is_some_and(u8::is_ascii_alphabetic))
+        || one_rest.is_some_and(u8::is_ascii_alphabetic)
+    {
+        Ordering::Less
+    } else {
+        Ordering::Greater
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cases of pacman's test/util/vercmptest.sh, each one is checked both ways
+    const CASES: &[(&str, &str, Ordering)] = &[
+        // all similar length, no pkgrel
+        ("1.5.0", "1.5.0", Ordering::Equal),
+        ("1.5.1", "1.5.0", Ordering::Greater),
+        // mixed length
+        ("1.5.1", "1.5", Ordering::Greater),
+        ("1.0.0", "1.0", Ordering::Greater),
+        // with pkgrel, simple
+        ("1.5.0-1", "1.5.0-1", Ordering::Equal),
+        ("1.5.0-1", "1.5.0-2", Ordering::Less),
+        ("1.5.0-1", "1.5.1-1", Ordering::Less),
+        ("1.5.0-2", "1.5.1-1", Ordering::Less),
+        // with pkgrel, mixed lengths
+        ("1.5-1", "1.5.1-1", Ordering::Less),
+        ("1.5-2", "1.5.1-1", Ordering::Less),
+        ("1.5-2", "1.5.1-2", Ordering::Less),
+        // mixed pkgrel inclusion
+        ("1.5", "1.5-1", Ordering::Equal),
+        ("1.5-1", "1.5", Ordering::Equal),
+        ("1.0-1", "1.0", Ordering::Equal),
+        ("1.1-1", "1.1", Ordering::Equal),
+        ("1.0-1", "1.1", Ordering::Less),
+        ("1.1-1", "1.0", Ordering::Greater),
+        // alphanumeric versions
+        ("1.5b-1", "1.5-1", Ordering::Less),
+        ("1.5b", "1.5", Ordering::Less),
+        ("1.5b-1", "1.5", Ordering::Less),
+        ("1.5b", "1.5.1", Ordering::Less),
+        // from the manpage
+        ("1.0a", "1.0alpha", Ordering::Less),
+        ("1.0alpha", "1.0b", Ordering::Less),
+        ("1.0b", "1.0beta", Ordering::Less),
+        ("1.0beta", "1.0rc", Ordering::Less),
+        ("1.0rc", "1.0", Ordering::Less),
+        ("1.0a", "1.0", Ordering::Less),
+        // alpha-dotted versions
+        ("1.5.a", "1.5", Ordering::Greater),
+        ("1.5.b", "1.5.a", Ordering::Greater),
+        ("1.5.1", "1.5.b", Ordering::Greater),
+        ("1.0", "1.0.a", Ordering::Less),
+        // alpha dots and dashes
+        ("1.5.b-1", "1.5.b", Ordering::Equal),
+        ("1.5-1", "1.5.b", Ordering::Less),
+        // same/similar content, differing separators
+        ("2.0", "2_0", Ordering::Equal),
+        ("2.0_a", "2_0.a", Ordering::Equal),
+        ("2.0a", "2.0.a", Ordering::Less),
+        ("2___a", "2_a", Ordering::Greater),
+        // epoch included version comparisons
+        ("0:1.0", "0:1.0", Ordering::Equal),
+        ("0:1.0", "0:1.1", Ordering::Less),
+        ("1:1.0", "0:1.0", Ordering::Greater),
+        ("1:1.0", "0:1.1", Ordering::Greater),
+        ("1:1.0", "2:1.1", Ordering::Less),
+        ("1:1.0", "2.0", Ordering::Greater),
+        // epoch + sometimes present pkgrel
+        ("1:1.0", "0:1.0-1", Ordering::Greater),
+        ("1:1.0-1", "0:1.1-1", Ordering::Greater),
+        // epoch included on one version
+        ("0:1.0", "1.0", Ordering::Equal),
+        ("0:1.0", "1.1", Ordering::Less),
+        ("0:1.1", "1.0", Ordering::Greater),
+        ("1:1.0", "1.0", Ordering::Greater),
+        ("1:1.0", "1.1", Ordering::Greater),
+        ("1:1.1", "1.1", Ordering::Greater),
+    ];
+
+    #[test]
+    fn vercmp_matches_pacman() {
+        for (a, b, expected) in CASES {
+            assert_eq!(vercmp(a, b), *expected, "vercmp {} {}", a, b);
+            assert_eq!(vercmp(b, a), expected.reverse(), "vercmp {} {}", b, a);
+        }
+    }
+}